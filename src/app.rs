@@ -1,8 +1,14 @@
 mod command;
+mod config;
+
+use std::env;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{parser::ValueSource, ArgMatches, CommandFactory, FromArgMatches, Parser};
 use command::Command;
+use config::Config;
+
+use crate::summary::OperationSummary;
 
 #[derive(Debug, Parser)]
 #[command(
@@ -26,7 +32,7 @@ pub struct ConfigArgs {
     /// When to use colors
     ///
     /// Declare when to use color for the pattern match output:
-    ///    'auto':      show colors if the output goes to an interactive console
+    ///    'auto':      show colors if the output goes to an interactive console and $NO_COLOR is not set
     ///    'never':     do not use colorized output
     ///    'always':    always use colorized output,
     #[arg(
@@ -65,6 +71,29 @@ pub struct ConfigArgs {
         verbatim_doc_comment,
     )]
     pub table_status: Status,
+
+    /// Print what put, restore, and empty did in one line
+    ///
+    /// The line tells how many items were handled and how large they are,
+    /// like 'Moved 3 items to the trash (1.2 KiB)'.
+    #[arg(long, verbatim_doc_comment)]
+    pub summary: bool,
+}
+
+impl ConfigArgs {
+    /// Whether to use colors, which `$NO_COLOR` turns off unless they were asked for.
+    pub fn use_color(&self, is_atty: bool) -> bool {
+        let no_color = env::var_os("NO_COLOR").map_or(false, |value| !value.is_empty());
+        if self.color_status == Status::Auto && no_color {
+            return false;
+        }
+        self.color_status.merge(is_atty)
+    }
+
+    /// The hook that prints the summary of a batch operation, if '--summary' was given.
+    pub fn on_complete(&self) -> Option<impl FnOnce(OperationSummary)> {
+        self.summary.then_some(|summary: OperationSummary| println!("{summary}"))
+    }
 }
 
 #[derive(clap::ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
@@ -92,6 +121,31 @@ pub enum TimeDisplayMode {
 }
 
 impl Args {
+    /// Parses the command line, filling in the flags it leaves out from the config file.
+    pub fn parse_with_config() -> Result<Self> {
+        let matches = Args::command().get_matches();
+        let mut args = Args::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+        args.apply_config(&Config::load()?, &matches);
+        Ok(args)
+    }
+
+    fn apply_config(&mut self, config: &Config, matches: &ArgMatches) {
+        let given = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+        let config_args = &mut self.config_args;
+        if let (Some(color), false) = (config.color, given("color_status")) {
+            config_args.color_status = color;
+        }
+        if let (Some(table), false) = (config.table, given("table_status")) {
+            config_args.table_status = table;
+        }
+        if let (Some(time), false) = (config.time, given("time_display_mode")) {
+            config_args.time_display_mode = time;
+        }
+        if let Some((_, matches)) = matches.subcommand() {
+            self.command.apply_config(config, matches);
+        }
+    }
+
     pub fn run(self) -> Result<()> {
         self.command.run(&self.config_args)?;
         Ok(())
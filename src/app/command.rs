@@ -1,13 +1,19 @@
+mod cat;
 mod completions;
 mod empty;
 mod list;
 mod manpage;
+mod migrate;
+mod optimize;
 pub mod put;
 mod restore;
 mod utils;
+mod which;
 
 use anyhow::Result;
-use clap::Parser;
+use clap::{parser::ValueSource, ArgMatches, Parser};
+
+use super::config::Config;
 
 #[derive(Parser, Debug)]
 pub enum Command {
@@ -23,6 +29,18 @@ pub enum Command {
     /// Restore files
     Restore(restore::Args),
 
+    /// Moves the home trash to another directory
+    Migrate(migrate::Args),
+
+    /// Prints the contents of a trashed file
+    Cat(cat::Args),
+
+    /// Rebuilds the caches of the trash directories
+    Optimize(optimize::Args),
+
+    /// Shows where files would be put
+    Which(which::Args),
+
     /// Generates completion for a shell
     Completions(completions::Args),
 
@@ -31,6 +49,27 @@ pub enum Command {
 }
 
 impl Command {
+    /// Uses the defaults from `config` for the flags that are not given in `matches`.
+    pub fn apply_config(&mut self, config: &Config, matches: &ArgMatches) {
+        let given = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+        use Command::*;
+        match self {
+            List(args) => args.query_args.apply_config(config, matches),
+            Empty(args) => args.query_args.apply_config(config, matches),
+            Restore(args) => args.query_args.apply_config(config, matches),
+            Cat(args) => args.rev = config.rev == Some(true),
+            Put(args) => {
+                if args.quota.max_bytes.is_none() {
+                    args.quota.max_bytes = config.max_trash_size;
+                }
+                if let (Some(policy), false) = (config.quota_policy, given("policy")) {
+                    args.quota.policy = Some(policy);
+                }
+            }
+            _ => (),
+        }
+    }
+
     pub fn run(self, config_args: &super::ConfigArgs) -> Result<()> {
         use Command::*;
         match self {
@@ -38,6 +77,10 @@ impl Command {
             Put(args) => args.run(config_args),
             Empty(args) => args.run(config_args),
             Restore(args) => args.run(config_args),
+            Cat(args) => args.run(),
+            Migrate(args) => args.run(),
+            Optimize(args) => args.run(),
+            Which(args) => args.run(),
             Completions(args) => args.run(),
             Manpage(args) => args.run(),
         }
@@ -0,0 +1,65 @@
+use std::io::{self, Read};
+
+use anyhow::{bail, Context, Result};
+use clap::Parser;
+
+use crate::{filter::Filters, utils};
+
+use super::list;
+
+/// Files larger than this are only printed with '--force'.
+const MAX_SIZE: u64 = 1024 * 1024;
+
+/// How much of a file is checked for NUL bytes to tell whether it is binary.
+const BINARY_CHECK_LEN: u64 = 8 * 1024;
+
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// The index of the item, as shown in the 'i' column of 'trashy list'
+    index: u32,
+
+    /// Use the indices of 'trashy list --all-mounts'
+    #[arg(long)]
+    all_mounts: bool,
+
+    /// Print the file even if it is large or binary
+    #[arg(short, long)]
+    force: bool,
+
+    /// Count the indices from the oldest item like 'trashy list' does when the config file sets
+    /// 'rev'
+    #[arg(skip)]
+    pub rev: bool,
+}
+
+impl Args {
+    pub fn run(&self) -> Result<()> {
+        let scope = if self.all_mounts { list::Scope::AllMounts } else { list::Scope::Home };
+        let items = list::list(scope, self.rev, None, Filters(Vec::new()))?;
+        let item = items
+            .get(self.index as usize)
+            .with_context(|| format!("There is no item with the index {}", self.index))?;
+        let mut file = list::open_reader(item)?;
+        if !self.force {
+            let len = file.metadata()?.len();
+            if len > MAX_SIZE {
+                bail!(
+                    "{} is {}, use --force to print it anyway",
+                    utils::path::display(&item.original_path()),
+                    utils::human_size(len)
+                );
+            }
+            let mut start = Vec::new();
+            (&mut file).take(BINARY_CHECK_LEN).read_to_end(&mut start)?;
+            if start.contains(&0) {
+                bail!(
+                    "{} looks like a binary file, use --force to print it anyway",
+                    utils::path::display(&item.original_path())
+                );
+            }
+            io::copy(&mut start.as_slice(), &mut io::stdout().lock())?;
+        }
+        io::copy(&mut file, &mut io::stdout().lock())?;
+        Ok(())
+    }
+}
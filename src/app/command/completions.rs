@@ -1,19 +1,116 @@
-use std::io;
+use std::io::{self, Write};
 
-use crate::app;
+use super::list;
+use crate::{app, utils};
 use anyhow::Result;
 use clap::{CommandFactory, Parser};
+use clap_complete::Shell;
+
+/// The maximum number of restore candidates offered to the shell at once.
+const MAX_RESTORE_CANDIDATES: usize = 100;
+
+const BASH_RESTORE_COMPLETION: &str = r#"
+_trashy_with_restore_candidates() {
+    _trashy "$@"
+    local cur="${COMP_WORDS[COMP_CWORD]}" i candidate
+    [[ "${cur}" == -* ]] && return
+    # global options like '-c never' may come before the subcommand
+    for (( i = 1; i < COMP_CWORD; i++ )); do
+        if [[ "${COMP_WORDS[i]}" == "restore" ]]; then
+            while IFS= read -r candidate; do
+                COMPREPLY+=( "$(printf '%q' "${candidate}")" )
+            done < <(trashy completions --restore-candidates "${cur}" 2>/dev/null)
+            return
+        fi
+    done
+}
+
+complete -F _trashy_with_restore_candidates -o bashdefault -o default trashy
+"#;
+
+const FISH_RESTORE_COMPLETION: &str = r#"
+complete -c trashy -n "__fish_seen_subcommand_from restore" -f -a "(trashy completions --restore-candidates (commandline -ct) 2>/dev/null)"
+"#;
 
 #[derive(Debug, Parser)]
 pub struct Args {
     /// shell to generate completions for
-    #[arg(value_enum)]
-    pub shell: clap_complete::Shell,
+    #[arg(value_enum, required_unless_present = "restore_candidates")]
+    pub shell: Option<Shell>,
+
+    /// Print the original paths of trashed items starting with a prefix
+    ///
+    /// This is used by the generated completions to offer candidates for 'trashy restore'.
+    /// The paths are printed as regexes matching only themselves, like the patterns of
+    /// 'trashy restore' are read by default.
+    #[arg(long, value_name = "PREFIX", hide = true)]
+    pub restore_candidates: Option<String>,
 }
 
 impl Args {
     pub fn run(&self) -> Result<()> {
-        clap_complete::generate(self.shell, &mut app::Args::command(), "trashy", &mut io::stdout());
+        if let Some(prefix) = &self.restore_candidates {
+            let mut stdout = io::stdout().lock();
+            for candidate in complete_restore_candidates(prefix) {
+                writeln!(stdout, "{}", regex::escape(&candidate))?;
+            }
+            return Ok(());
+        }
+        let shell = self.shell.expect("BUG: clap requires a shell");
+        let mut stdout = io::stdout();
+        clap_complete::generate(shell, &mut app::Args::command(), "trashy", &mut stdout);
+        match shell {
+            Shell::Bash => write!(stdout, "{BASH_RESTORE_COMPLETION}")?,
+            Shell::Fish => write!(stdout, "{FISH_RESTORE_COMPLETION}")?,
+            _ => (),
+        }
         Ok(())
     }
 }
+
+/// Returns the original paths of items in the home trash that start with `prefix`.
+///
+/// `prefix` may also be the start of a path escaped with `regex::escape`, like a candidate that
+/// was completed before. This runs on every tab press, so errors are swallowed and the list is
+/// capped.
+pub fn complete_restore_candidates(prefix: &str) -> Vec<String> {
+    let items = match list::list_scoped(list::Scope::Home) {
+        Ok(items) => items,
+        Err(_) => return Vec::new(),
+    };
+    let mut candidates: Vec<_> = items
+        .iter()
+        .map(|item| utils::path::display(&item.original_path()))
+        .filter(|path| path.starts_with(prefix) || regex::escape(path).starts_with(prefix))
+        .collect();
+    candidates.sort_unstable();
+    candidates.dedup();
+    candidates.truncate(MAX_RESTORE_CANDIDATES);
+    candidates
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::fs;
+
+    use super::super::utils::use_home_trash_in;
+    use super::*;
+
+    #[test]
+    fn restore_candidates() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        let paths = [dir.path().join("file (1).txt"), dir.path().join("a+b.txt")];
+        for path in &paths {
+            fs::write(path, "").unwrap();
+            trash::delete(path).unwrap();
+        }
+
+        let display = |path: &std::path::PathBuf| utils::path::display(path);
+        let prefix = format!("{}/file (", dir.path().display());
+        assert_eq!(complete_restore_candidates(&prefix), [display(&paths[0])]);
+        // a pattern that was completed before is escaped
+        let escaped = regex::escape(&format!("{}/a+", dir.path().display()));
+        assert_eq!(complete_restore_candidates(&escaped), [display(&paths[1])]);
+    }
+}
@@ -1,33 +1,83 @@
+use std::{
+    env,
+    io::{self, BufRead},
+    path::PathBuf,
+};
+
 use anyhow::Result;
 use clap::Parser;
 use either::Either::*;
 
-use crate::{app, trash_item::MaybeIndexedTrashItems};
+use crate::{
+    app, print,
+    summary::{Operation, OperationSummary},
+    trash_dir::EmptyReport,
+    trash_item::MaybeIndexedTrashItems,
+    utils,
+};
 
 use super::{
     list,
-    utils::{Force, Ranges},
+    utils::{Force, Interactive, Ranges},
 };
 
 #[derive(Parser, Debug)]
 pub struct Args {
     #[clap(flatten)]
-    query_args: list::QueryArgs,
+    pub(super) query_args: list::QueryArgs,
 
     /// Empty all files
     #[arg(long, conflicts_with_all = list::QueryArgs::CONFLICTS)]
     all: bool,
 
+    /// Empty the items whose trashed names are read from stdin
+    ///
+    /// Every line is the name of an item in the files directory of a trash directory,
+    /// as shown by 'trashy list --stat' or 'trashy list --manifest'.
+    /// Names that are not found are reported and skipped.
+    /// There is no confirmation, so this requires '--force'.
+    #[arg(
+        long,
+        requires = "force",
+        conflicts_with_all = list::QueryArgs::CONFLICTS,
+        conflicts_with_all = ["all", "ranges", "interactive"],
+        verbatim_doc_comment
+    )]
+    from_stdin: bool,
+
+    /// Empty the items that were trashed from these paths
+    ///
+    /// A file that was trashed several times is emptied every time it is in the trash.
+    /// Paths that no item was trashed from are reported and skipped.
+    /// There is no confirmation, so this requires '--force'.
+    #[arg(
+        long,
+        value_name = "PATH",
+        requires = "force",
+        conflicts_with_all = list::QueryArgs::CONFLICTS,
+        conflicts_with_all = ["all", "ranges", "interactive", "from_stdin"],
+        verbatim_doc_comment
+    )]
+    original: Vec<PathBuf>,
+
     #[clap(flatten)]
     ranges: Ranges,
 
     #[clap(flatten)]
     force: Force,
+
+    #[clap(flatten)]
+    interactive: Interactive,
 }
 
 impl Args {
     pub fn run(&self, config_args: &app::ConfigArgs) -> Result<()> {
-        let empty: Box<dyn Fn(_) -> _> = if self.force.force {
+        let empty = |items| empty(items, config_args.on_complete());
+        let empty: Box<dyn Fn(_) -> _> = if self.interactive.interactive {
+            Box::new(|items| {
+                super::utils::on_each_item_with_prompt(items, config_args, "emptied", empty)
+            })
+        } else if self.force.force {
             Box::new(empty)
         } else {
             Box::new(|items| {
@@ -35,8 +85,21 @@ impl Args {
             })
         };
 
+        if self.from_stdin {
+            return empty_from_stdin(self.query_args.scope());
+        }
+        if !self.original.is_empty() {
+            return empty_by_original(self.query_args.scope(), &self.original);
+        }
         if self.all {
-            empty(MaybeIndexedTrashItems(Left(list::list_only()?)))?
+            if list::is_empty(self.query_args.scope())? {
+                println!("The trash is already empty");
+                return Ok(());
+            }
+            if !self.force.force && cfg!(target_os = "linux") {
+                print_preview(self.query_args.scope())?;
+            }
+            empty(MaybeIndexedTrashItems(Left(list::list_only(self.query_args.scope())?)))?
         } else if self.ranges.ranges.is_empty() {
             empty(MaybeIndexedTrashItems(Left(self.query_args.list(true)?)))?
         } else {
@@ -48,7 +111,79 @@ impl Args {
     }
 }
 
-fn empty(items: MaybeIndexedTrashItems) -> Result<()> {
-    trash::os_limited::purge_all(items.items())?;
+fn empty(
+    items: MaybeIndexedTrashItems,
+    on_complete: Option<impl FnOnce(OperationSummary)>,
+) -> Result<()> {
+    let items: Vec<_> = items.items().collect();
+    // the sizes can't be read anymore once the items are gone
+    let sizes: Vec<_> = match on_complete {
+        Some(_) => items.iter().map(list::item_size).collect(),
+        None => Vec::new(),
+    };
+    trash::os_limited::purge_all(items)?;
+    if let Some(on_complete) = on_complete {
+        on_complete(OperationSummary::new(Operation::Empty, sizes));
+    }
+    Ok(())
+}
+
+/// Tells how much space emptying every item in `scope` frees before asking for confirmation.
+fn print_preview(scope: list::Scope) -> Result<()> {
+    let mut total = EmptyReport::default();
+    for trash_dir in scope.trash_dirs() {
+        let report = trash_dir.empty_preview()?;
+        total.items += report.items;
+        total.size += report.size;
+    }
+    let plural = if total.items == 1 { "" } else { "s" };
+    println!(
+        "This will free {} across {} item{plural}",
+        utils::human_size(total.size),
+        total.items
+    );
+    Ok(())
+}
+
+fn empty_from_stdin(scope: list::Scope) -> Result<()> {
+    let mut names = Vec::new();
+    for line in io::stdin().lock().lines() {
+        let line = line?;
+        let name = line.strip_suffix('\r').unwrap_or(&line);
+        if !name.is_empty() {
+            names.push(name.to_owned());
+        }
+    }
+    let mut remaining: Vec<&str> = names.iter().map(String::as_str).collect();
+    for trash_dir in scope.trash_dirs() {
+        if remaining.is_empty() {
+            break;
+        }
+        let report = trash_dir.purge_by_names(&remaining)?;
+        remaining.retain(|name| report.not_found.iter().any(|not_found| not_found == name));
+    }
+    for name in remaining {
+        print::warn_display(format!("No item is trashed as {name}"));
+    }
+    Ok(())
+}
+
+fn empty_by_original(scope: list::Scope, paths: &[PathBuf]) -> Result<()> {
+    let current_dir = env::current_dir()?;
+    for path in paths {
+        let path = utils::path::normalize(&current_dir.join(path));
+        let mut purged = 0;
+        for trash_dir in scope.trash_dirs() {
+            purged += trash_dir.purge_by_original(&path)?.purged;
+        }
+        match purged {
+            0 => print::warn_display(format!(
+                "Nothing was trashed from {}",
+                utils::path::display(&path)
+            )),
+            1 => (),
+            n => println!("Emptied {n} items trashed from {}", utils::path::display(&path)),
+        }
+    }
     Ok(())
 }
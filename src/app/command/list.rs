@@ -1,13 +1,16 @@
 use std::borrow::Cow;
+use std::cmp;
+use std::collections::{BTreeMap, BinaryHeap, HashSet};
 use std::io::Write;
 use std::num::NonZeroU32;
+use std::time::Duration;
 use std::{
-    cmp, fs, io,
+    env, fs, io,
     path::{Path, PathBuf},
 };
 
 use chrono::{Local, TimeZone};
-use clap::Parser;
+use clap::{parser::ValueSource, ArgMatches, Parser, ValueEnum};
 use tabled::{width::Truncate, Table, Tabled};
 
 use anyhow::{bail, Context, Result};
@@ -17,24 +20,300 @@ use crate::app::TimeDisplayMode;
 use crate::filter::Filters;
 use crate::{
     app,
+    clock::{Clock, SystemClock},
     filter::FilterArgs,
     range_set::RangeSet,
+    trash_dir::{self, merged::MergedTrashIter, BrokenEntry, TrashDir},
+    trash_info, trash_item,
     utils::{self, swap},
 };
 
 #[derive(Parser, Debug)]
 pub struct Args {
     #[clap(flatten)]
-    query_args: QueryArgs,
+    pub(super) query_args: QueryArgs,
+
+    /// Only show items that were trashed from the same path more than once
+    #[arg(long)]
+    duplicates: bool,
+
+    /// Show the info files that could not be read instead of the items
+    ///
+    /// Items with a broken info file are left out of every other command.
+    /// This lists each of those info files together with what is wrong with it.
+    #[arg(long, conflicts_with = "duplicates", verbatim_doc_comment)]
+    broken: bool,
+
+    /// Show how many items are in each trash directory instead of the items
+    ///
+    /// This looks at the trash directories of every mounted drive, like '--all-mounts'.
+    #[arg(long, conflicts_with_all = ["duplicates", "broken"], verbatim_doc_comment)]
+    by_volume: bool,
+
+    /// Show how many items were trashed from each directory instead of the items
+    #[arg(long, conflicts_with_all = ["duplicates", "broken", "by_volume"])]
+    by_parent: bool,
+
+    /// Show how much space the trashed files use in each trash directory instead of the items
+    ///
+    /// This is what emptying the trash frees. Info files and caches are not counted, but
+    /// trashed files without an info file are.
+    #[arg(
+        long,
+        conflicts_with_all = QueryArgs::CONFLICTS,
+        conflicts_with_all = ["duplicates", "broken", "by_volume", "by_parent"],
+        verbatim_doc_comment
+    )]
+    usage: bool,
+
+    /// Show the trashed files that have no info file instead of the items
+    ///
+    /// Their original path is unknown, so they can only be recovered with 'trashy restore --orphans-to'.
+    #[arg(long, conflicts_with_all = ["duplicates", "broken", "by_volume"], verbatim_doc_comment)]
+    orphans: bool,
+
+    /// Show the items whose trashed files can't be read instead of the items
+    ///
+    /// Every file inside trashed directories is opened too, which finds data that was lost to
+    /// a broken drive before relying on restoring it.
+    #[arg(long, conflicts_with_all = ["duplicates", "broken", "by_volume", "orphans"], verbatim_doc_comment)]
+    verify: bool,
+
+    /// Read the files to the end with '--verify' instead of only opening them
+    #[arg(long, requires = "verify")]
+    full_read: bool,
+
+    /// Only show the 'n' largest items, from largest to smallest
+    ///
+    /// The largest item gets the index 0, and items of the same size are sorted newest first.
+    /// Combine this with '--stat' to see the sizes.
+    #[arg(
+        long,
+        value_name = "N",
+        conflicts_with_all = ["broken", "by_volume", "orphans"],
+        verbatim_doc_comment
+    )]
+    largest: Option<usize>,
+
+    /// Show the details of every item as a block instead of a table
+    ///
+    /// For each item this shows its original path, its name inside the trash, when it was trashed,
+    /// its size and type, whether both the trashed file and its info file exist,
+    /// and whether restoring it would move it to another filesystem.
+    #[arg(long, conflicts_with_all = ["broken", "by_volume", "orphans"], verbatim_doc_comment)]
+    stat: bool,
+
+    /// Write a manifest of the items instead of showing them
+    ///
+    /// The manifest has the original path, the name inside the trash, the deletion date,
+    /// and the size of every item, without their contents.
+    #[arg(
+        long,
+        value_enum,
+        value_name = "FORMAT",
+        conflicts_with_all = ["broken", "by_volume", "orphans", "stat"],
+        verbatim_doc_comment
+    )]
+    manifest: Option<ManifestFormat>,
+
+    /// Show paths as they are written in the trash info files
+    ///
+    /// The paths are percent-encoded, which helps to debug encoding issues.
+    #[arg(long, verbatim_doc_comment)]
+    raw_path: bool,
+
+    /// Show paths under the home directory starting with '~'
+    #[arg(long, conflicts_with = "raw_path")]
+    tilde: bool,
+
+    /// Only show items that were trashed within this duration, like 7d or 12h
+    ///
+    /// Unlike '--within', this skips the info files that were last changed before it without
+    /// reading them, which is much faster in a large trash. An item whose info file has an older
+    /// modification time than its deletion date can be left out by this.
+    #[arg(
+        long,
+        value_name = "DURATION",
+        value_parser = humantime::parse_duration,
+        conflicts_with_all = ["broken", "by_volume", "orphans"],
+        verbatim_doc_comment
+    )]
+    max_age: Option<Duration>,
+
+    /// Add a column with the size of every item
+    #[arg(long, conflicts_with_all = ["broken", "by_volume", "orphans", "stat", "manifest"])]
+    size: bool,
+
+    /// Add a column with who or what trashed every item
+    ///
+    /// This is the label given to 'trashy put --label', which is empty for other items.
+    #[arg(
+        long,
+        conflicts_with_all = ["broken", "by_volume", "orphans", "stat", "manifest"],
+        verbatim_doc_comment
+    )]
+    show_source: bool,
+
+    /// Only show the items that were put into this namespace
+    ///
+    /// This is the namespace given to 'trashy put --namespace'.
+    #[arg(
+        long,
+        value_name = "NS",
+        conflicts_with_all = ["broken", "by_volume", "orphans"],
+        verbatim_doc_comment
+    )]
+    namespace: Option<String>,
+
+    /// Keep running and show the items that are added or removed
+    ///
+    /// After the table, every item that is added to a trash directory is shown as '+ <name>',
+    /// and every item that is removed as '- <name>', using its name inside the trash.
+    #[cfg(feature = "watch")]
+    #[arg(
+        long,
+        conflicts_with_all = ["broken", "by_volume", "orphans", "stat", "manifest"],
+        verbatim_doc_comment
+    )]
+    watch: bool,
 }
 
 impl Args {
     pub fn run(&self, config_args: &app::ConfigArgs) -> Result<()> {
-        display_items(&self.query_args.list(false)?, config_args)?;
+        if self.broken {
+            let mut broken = read_scoped(self.query_args.scope())?.1;
+            broken.sort_by(|a, b| a.info_path.cmp(&b.info_path));
+            let mut stdout = io::stdout().lock();
+            for entry in broken {
+                writeln!(stdout, "{}: {:#}", entry.info_path.display(), entry.error)?;
+            }
+            return Ok(());
+        }
+        if self.orphans {
+            let mut stdout = io::stdout().lock();
+            for path in list_orphans(self.query_args.scope())? {
+                writeln!(stdout, "{}", utils::path::display(&path))?;
+            }
+            return Ok(());
+        }
+        if self.by_parent {
+            let mut stdout = io::stdout().lock();
+            for (parent, count) in count_by_parent(self.query_args.scope())? {
+                writeln!(stdout, "{} ({count})", utils::path::display(&parent))?;
+            }
+            return Ok(());
+        }
+        if self.verify {
+            return verify(self.query_args.scope(), self.full_read);
+        }
+        if self.by_volume {
+            let filters = self.query_args.filter_args.to_filters()?;
+            let mut stdout = io::stdout().lock();
+            for (trash_dir, items) in list_by_volume()? {
+                let count = items.iter().filter(|item| filters.is_match(item)).count();
+                let name = if trash_dir.is_home() {
+                    "Home trash".into()
+                } else {
+                    utils::path::display(trash_dir.path())
+                };
+                writeln!(stdout, "{name} ({count})")?;
+            }
+            return Ok(());
+        }
+        if self.usage {
+            let mut stdout = io::stdout().lock();
+            let mut total = 0;
+            for trash_dir in self.query_args.scope().trash_dirs() {
+                let used = trash_dir.used_space()?;
+                total += used;
+                let name = if trash_dir.is_home() {
+                    "Home trash".into()
+                } else {
+                    utils::path::display(trash_dir.path())
+                };
+                writeln!(stdout, "{name}: {}", utils::human_size(used))?;
+            }
+            writeln!(stdout, "Total: {}", utils::human_size(total))?;
+            return Ok(());
+        }
+        let mut items = match self.max_age {
+            Some(max_age) => self.query_args.list_since(max_age)?,
+            None => self.query_args.list(false)?,
+        };
+        if self.duplicates {
+            let duplicates = trash_item::find_duplicates(&items)
+                .into_iter()
+                .flatten()
+                .map(|item| item.id.clone())
+                .collect::<HashSet<_>>();
+            items.retain(|item| duplicates.contains(&item.id));
+        }
+        if let Some(ns) = &self.namespace {
+            items.retain(|item| namespace(item).as_deref() == Some(ns.as_str()));
+        }
+        if let Some(n) = self.largest {
+            items = largest(items, n);
+        }
+        if self.stat {
+            return display_stats(&items, config_args);
+        }
+        if let Some(format) = self.manifest {
+            return export_manifest(&items, io::stdout().lock(), format);
+        }
+        let path_display = if self.raw_path {
+            PathDisplay::Raw
+        } else if self.tilde {
+            PathDisplay::Tilde
+        } else {
+            PathDisplay::Decoded
+        };
+        let columns = TableColumns {
+            show_size: self.size,
+            show_source: self.show_source,
+            ..TableColumns::default()
+        };
+        display_items_as(&items, config_args, path_display, columns)?;
+        #[cfg(feature = "watch")]
+        if self.watch {
+            watch(self.query_args.scope())?;
+        }
         Ok(())
     }
 }
 
+/// Prints the items that are added to or removed from `scope` until this is interrupted.
+#[cfg(feature = "watch")]
+fn watch(scope: Scope) -> Result<()> {
+    use std::{sync::mpsc, thread};
+
+    use crate::trash_dir::watch::TrashEvent;
+
+    let (sender, receiver) = mpsc::channel();
+    for trash_dir in scope.trash_dirs() {
+        // a trash directory that does not exist yet is not watched
+        if let Ok(events) = trash_dir.watch() {
+            let sender = sender.clone();
+            thread::spawn(move || {
+                events.take_while(|event| sender.send(event.clone()).is_ok()).count()
+            });
+        }
+    }
+    drop(sender);
+    let mut stdout = io::stdout().lock();
+    for event in receiver {
+        match event {
+            TrashEvent::Added(name) => {
+                writeln!(stdout, "+ {}", utils::path::display(Path::new(&name)))?
+            }
+            TrashEvent::Removed(name) => {
+                writeln!(stdout, "- {}", utils::path::display(Path::new(&name)))?
+            }
+        }
+        stdout.flush()?;
+    }
+    Ok(())
+}
+
 #[derive(Debug, Parser)]
 pub struct QueryArgs {
     #[clap(flatten)]
@@ -47,9 +326,13 @@ pub struct QueryArgs {
     /// This will also affect 'empty' or 'restore' if used in either command.
     /// Examples:
     /// 'trashy empty --rev -n=10' will delete 10 oldest trash items are deleted.
-    #[arg(long, verbatim_doc_comment)]
+    #[arg(long, overrides_with = "no_rev", verbatim_doc_comment)]
     pub rev: bool,
 
+    /// Keep the newest trash items at the bottom, even if the config file sets 'rev'
+    #[arg(long, overrides_with = "rev")]
+    pub no_rev: bool,
+
     /// Show 'n' maximum trash items
     ///
     /// This will also affect 'empty' or 'restore' if used in either command.
@@ -58,6 +341,14 @@ pub struct QueryArgs {
     /// 'trashy restore -n=10' will list restore the ten newest trashed items.
     #[arg(short = 'n', long = "max", verbatim_doc_comment)]
     pub max: Option<NonZeroU32>,
+
+    /// Operate on every trash directory
+    ///
+    /// By default only the home trash is used.
+    /// This option also includes the trash directories of every mounted drive.
+    /// This will also affect 'empty' or 'restore' if used in either command.
+    #[arg(long, verbatim_doc_comment)]
+    pub all_mounts: bool,
 }
 
 impl QueryArgs {
@@ -71,6 +362,7 @@ impl QueryArgs {
         "patterns",
         "match",
         "rev",
+        "no_rev",
         "max",
         "directories",
     ];
@@ -80,7 +372,19 @@ impl QueryArgs {
         if non_empty && filters.is_empty() {
             bail!("Must match something");
         }
-        list(self.rev, self.max, filters)
+        list(self.scope(), self.rev, self.max, filters)
+    }
+
+    /// Like `list`, but only with the items that were trashed within `max_age`.
+    pub fn list_since(&self, max_age: Duration) -> Result<Vec<TrashItem>> {
+        self.list_since_with(&SystemClock, max_age)
+    }
+
+    /// Like `list_since`, but with `max_age` and the filters relative to `clock`.
+    pub fn list_since_with(&self, clock: &impl Clock, max_age: Duration) -> Result<Vec<TrashItem>> {
+        let filters = self.filter_args.to_filters_with(clock)?;
+        let cutoff = clock.now_local().timestamp() - max_age.as_secs() as i64;
+        Ok(process_items(self.rev, self.max, filters, list_since(self.scope(), cutoff)?))
     }
 
     pub fn list_ranged(&self, non_empty: bool, ranges: RangeSet) -> Result<Vec<(u32, TrashItem)>> {
@@ -88,18 +392,157 @@ impl QueryArgs {
         if non_empty && filters.is_empty() {
             bail!("Must match something");
         }
-        list_ranged(self.rev, self.max, filters, ranges)
+        list_ranged(self.scope(), self.rev, self.max, filters, ranges)
+    }
+
+    /// Uses `rev` from the config file unless '--rev' or '--no-rev' is given in `matches`.
+    pub fn apply_config(&mut self, config: &app::config::Config, matches: &ArgMatches) {
+        let given = |id| matches.value_source(id) == Some(ValueSource::CommandLine);
+        if let (Some(rev), false) = (config.rev, given("rev") || given("no_rev")) {
+            self.rev = rev;
+        }
+    }
+
+    pub fn scope(&self) -> Scope {
+        if self.all_mounts {
+            Scope::AllMounts
+        } else {
+            Scope::Home
+        }
+    }
+}
+
+/// Which trash directories to operate on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Scope {
+    Home,
+    AllMounts,
+}
+
+impl Scope {
+    pub fn trash_dirs(self) -> Vec<TrashDir> {
+        match self {
+            Scope::Home => TrashDir::home().into_iter().collect(),
+            Scope::AllMounts => trash_dir::all_trash_dirs(),
+        }
     }
 }
 
-pub fn list_only() -> Result<Vec<TrashItem>> {
-    let mut items = trash::os_limited::list()?;
-    items.sort_by_key(|item| cmp::Reverse(item.time_deleted));
-    Ok(items)
+/// Whether there are no items in `scope`, without reading all of them on linux.
+pub fn is_empty(scope: Scope) -> Result<bool> {
+    if !cfg!(target_os = "linux") {
+        return Ok(trash::os_limited::list()?.is_empty());
+    }
+    trash_dir::all_empty(&scope.trash_dirs())
+}
+
+pub fn list_scoped(scope: Scope) -> Result<Vec<TrashItem>> {
+    Ok(read_scoped(scope)?.0)
+}
+
+/// Reads the items of every trash directory, grouped by the directory they are in.
+///
+/// Trash directories of drives that are not mounted don't appear.
+pub fn list_by_volume() -> Result<Vec<(TrashDir, Vec<TrashItem>)>> {
+    if !cfg!(target_os = "linux") {
+        bail!("Listing by volume is only supported on linux");
+    }
+    Ok(trash_dir::all_trash_dirs()
+        .into_iter()
+        .map(|trash_dir| {
+            let items = trash_dir.read().0;
+            (trash_dir, items)
+        })
+        .collect())
+}
+
+/// Reads the items in the trash directories of `scope`, along with the info files that are broken.
+pub fn read_scoped(scope: Scope) -> Result<(Vec<TrashItem>, Vec<BrokenEntry>)> {
+    if !cfg!(target_os = "linux") {
+        return Ok((trash::os_limited::list()?, Vec::new()));
+    }
+    let mut items = Vec::new();
+    let mut broken = Vec::new();
+    for (_, entry) in MergedTrashIter::new(scope.trash_dirs()) {
+        match entry {
+            Ok(item) => items.push(item),
+            Err(entry) => broken.push(entry),
+        }
+    }
+    Ok((items, broken))
+}
+
+/// Reads the items in `scope` that were trashed at or after `cutoff`, in seconds since the unix
+/// epoch, skipping old info files by their modification time on linux.
+pub fn list_since(scope: Scope, cutoff: i64) -> Result<Vec<TrashItem>> {
+    if !cfg!(target_os = "linux") {
+        let mut items = trash::os_limited::list()?;
+        items.retain(|item| item.time_deleted >= cutoff);
+        return Ok(items);
+    }
+    Ok(scope.trash_dirs().iter().flat_map(|trash_dir| trash_dir.read_since(cutoff).0).collect())
+}
+
+/// Counts the items in `scope` by the directory they were trashed from, sorted by directory.
+pub fn count_by_parent(scope: Scope) -> Result<BTreeMap<PathBuf, usize>> {
+    if !cfg!(target_os = "linux") {
+        bail!("Counting items by directory is only supported on linux");
+    }
+    let mut counts = BTreeMap::new();
+    for trash_dir in scope.trash_dirs() {
+        for (parent, count) in trash_dir.count_by_parent()? {
+            *counts.entry(parent).or_insert(0) += count;
+        }
+    }
+    Ok(counts)
+}
+
+/// Prints the items in `scope` whose trashed files can't be read, and fails if there are any.
+fn verify(scope: Scope, full_read: bool) -> Result<()> {
+    if !cfg!(target_os = "linux") {
+        bail!("Verifying trashed files is only supported on linux");
+    }
+    let mut stdout = io::stdout().lock();
+    let mut failed = 0;
+    for trash_dir in scope.trash_dirs() {
+        for entry in trash_dir.verify_readable(full_read)? {
+            let original = match trash_dir.original_location(&entry.info_path) {
+                Ok(path) => utils::path::display(&path),
+                Err(_) => "unknown original path".into(),
+            };
+            let path = utils::path::display(&entry.path);
+            writeln!(stdout, "{original} ({path}): {}", entry.error)?;
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        let plural = if failed == 1 { "" } else { "s" };
+        bail!("{failed} item{plural} can't be read");
+    }
+    Ok(())
 }
 
-pub fn list(rev: bool, max: Option<NonZeroU32>, filters: Filters) -> Result<Vec<TrashItem>> {
-    Ok(process_items(rev, max, filters, trash::os_limited::list()?))
+/// Returns the trashed files in `scope` that have no info file, sorted by path.
+pub fn list_orphans(scope: Scope) -> Result<Vec<PathBuf>> {
+    if !cfg!(target_os = "linux") {
+        bail!("Listing orphaned files is only supported on linux");
+    }
+    let mut orphans: Vec<_> = scope.trash_dirs().iter().flat_map(TrashDir::orphans).collect();
+    orphans.sort();
+    Ok(orphans)
+}
+
+pub fn list_only(scope: Scope) -> Result<Vec<TrashItem>> {
+    Ok(trash_item::newest_first(list_scoped(scope)?).collect())
+}
+
+pub fn list(
+    scope: Scope,
+    rev: bool,
+    max: Option<NonZeroU32>,
+    filters: Filters,
+) -> Result<Vec<TrashItem>> {
+    Ok(process_items(rev, max, filters, list_scoped(scope)?))
 }
 
 pub fn process_items(
@@ -108,16 +551,13 @@ pub fn process_items(
     filters: Filters,
     items: Vec<TrashItem>,
 ) -> Vec<TrashItem> {
-    let mut items = if !filters.is_empty() {
+    let items = if !filters.is_empty() {
         items.into_iter().filter(|item| filters.is_match(item)).collect()
     } else {
         items
     };
-    if rev {
-        items.sort_by_key(|item| item.time_deleted);
-    } else {
-        items.sort_by_key(|item| cmp::Reverse(item.time_deleted));
-    }
+    let items = trash_item::newest_first(items);
+    let items: Vec<_> = if rev { items.rev().collect() } else { items.collect() };
     match max {
         Some(n) => items.into_iter().take(n.get() as usize).collect(),
         None => items,
@@ -125,12 +565,13 @@ pub fn process_items(
 }
 
 pub fn list_ranged(
+    scope: Scope,
     rev: bool,
     max: Option<NonZeroU32>,
     filters: Filters,
     ranges: RangeSet,
 ) -> Result<Vec<(u32, TrashItem)>> {
-    let items = list(rev, max, filters)?;
+    let items = list(scope, rev, max, filters)?;
     filter_by_ranges(&items, ranges)
 }
 
@@ -151,21 +592,57 @@ pub fn filter_by_ranges(items: &[TrashItem], ranges: RangeSet) -> Result<Vec<(u3
     Ok(new_items)
 }
 
-pub fn display_items<'a>(items: &[TrashItem], config_args: &app::ConfigArgs) -> Result<()> {
-    display_indexed_items(items.iter().zip(0..items.len() as u32).map(swap), config_args)
+/// How the original path of an item is displayed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PathDisplay {
+    /// The decoded path
+    Decoded,
+    /// The percent-encoded `Path=` value of the info file
+    Raw,
+    /// The decoded path, starting with `~` when it is under the home directory
+    Tilde,
+}
+
+pub fn display_items(items: &[TrashItem], config_args: &app::ConfigArgs) -> Result<()> {
+    display_items_as(items, config_args, PathDisplay::Decoded, TableColumns::default())
+}
+
+pub fn display_items_as(
+    items: &[TrashItem],
+    config_args: &app::ConfigArgs,
+    path_display: PathDisplay,
+    columns: TableColumns,
+) -> Result<()> {
+    display_indexed_items_as(
+        items.iter().zip(0..items.len() as u32).map(swap),
+        config_args,
+        path_display,
+        columns,
+    )
 }
 
 pub fn display_indexed_items<'a>(
     items: impl DoubleEndedIterator<Item = (u32, &'a TrashItem)> + ExactSizeIterator,
     config_args: &app::ConfigArgs,
+) -> Result<()> {
+    display_indexed_items_as(items, config_args, PathDisplay::Decoded, TableColumns::default())
+}
+
+fn display_indexed_items_as<'a>(
+    items: impl DoubleEndedIterator<Item = (u32, &'a TrashItem)> + ExactSizeIterator,
+    config_args: &app::ConfigArgs,
+    path_display: PathDisplay,
+    columns: TableColumns,
 ) -> Result<()> {
     let is_atty = atty::is(atty::Stream::Stdout);
     display_indexed_items_with(
         items,
-        config_args.color_status.merge(is_atty),
+        config_args.use_color(is_atty),
         config_args.table_status.merge(is_atty),
         config_args.time_display_mode,
         Path::new(""),
+        path_display,
+        columns,
     )
 }
 
@@ -175,11 +652,25 @@ fn display_indexed_items_with<'a>(
     use_table: bool,
     time_display_mode: TimeDisplayMode,
     base: &Path,
+    path_display: PathDisplay,
+    columns: TableColumns,
 ) -> Result<()> {
     if items.len() == 0 {
         return Ok(());
     }
-    let table = indexed_items_to_table(items, use_color, use_table, time_display_mode, base)?;
+    let columns = TableColumns {
+        terminal_width: terminal_size::terminal_size().map(|(width, _)| width.0 as usize),
+        ..columns
+    };
+    let table = indexed_items_to_table(
+        items,
+        use_color,
+        use_table,
+        time_display_mode,
+        base,
+        path_display,
+        columns,
+    )?;
     writeln!(io::stdout(), "{table}").context("Printing table")?;
     Ok(())
 }
@@ -190,30 +681,70 @@ pub fn indexed_items_to_table<'a>(
     use_table: bool,
     time_display_mode: TimeDisplayMode,
     base: &Path,
+    path_display: PathDisplay,
+    columns: TableColumns,
 ) -> Result<Table> {
     let mut failed = 0; // 'failed' does not seem to be read anywhere except 197 line
 
+    // leave room for the index, the time, and the size, but always show some of the path
+    let max_width = columns.terminal_width.map(|width| {
+        let reserved =
+            30 + if columns.show_size { 12 } else { 0 } + if columns.show_source { 16 } else { 0 };
+        width.saturating_sub(reserved).max(MIN_PATH_WIDTH)
+    });
+
     // this isn't actually needless since we need to reverse the items, which can't be done with a single-ended iterator
-    let items = items
-        .filter_map(|(i, item)| match display_item(item, use_color, time_display_mode, base) {
-            Ok(s) => Some(TrashItemDisplay { i, time: s.0, path: s.1 }),
-            Err(_) => {
-                failed += 1;
-                None
+    let items: Vec<_> = items
+        .filter_map(|(i, item)| {
+            match display_item(item, use_color, time_display_mode, base, path_display, max_width) {
+                Ok(s) => Some(TrashItemDisplay {
+                    i,
+                    time: s.0,
+                    path: s.1,
+                    size: if columns.show_size {
+                        item_size(item).map_or_else(|| "unknown".into(), utils::human_size)
+                    } else {
+                        String::new()
+                    },
+                    trash: trash_dir::trash_dir_of(item)
+                        .map(|dir| utils::path::display(&dir))
+                        .unwrap_or_default(),
+                    source: if columns.show_source { deleted_by(item) } else { String::new() },
+                    in_home_trash: trash_dir::is_in_home_trash(item),
+                }),
+                Err(_) => {
+                    failed += 1;
+                    None
+                }
             }
         })
-        .rev();
+        .rev()
+        .collect();
+    // only annotate items with their trash directory when they come from more than the home trash
+    let show_trash = items.iter().any(|item| !item.in_home_trash);
     let mut table = Table::builder(items);
     if !use_table {
         table.remove_columns();
     };
-    use tabled::{object::Segment, Alignment, Modify};
+    use tabled::{
+        object::{Columns, Segment},
+        Alignment, Disable, Modify,
+    };
     let mut table = table.build();
     table.with(Modify::new(Segment::all()).with(Alignment::left()));
-    if let Some((terminal_size::Width(width), _)) = terminal_size::terminal_size() {
-        let width = width as usize;
-        table
-            .with(Modify::new(Segment::new(.., 2..)).with(Truncate::new(width - 30).suffix("...")));
+    table.with(Modify::new(Columns::single(3)).with(Alignment::right()));
+    if let Some(width) = max_width {
+        table.with(Modify::new(Columns::new(4..)).with(Truncate::new(width).suffix("…")));
+    }
+    // disable columns last and from the right, since that shifts the indices of the following ones
+    if !columns.show_source {
+        table.with(Disable::column(Columns::single(5)));
+    }
+    if !show_trash {
+        table.with(Disable::column(Columns::single(4)));
+    }
+    if !columns.show_size {
+        table.with(Disable::column(Columns::single(3)));
     }
     if use_table {
         table.with(tabled::Style::rounded());
@@ -228,32 +759,111 @@ pub fn display_item(
     color: bool,
     time_display_mode: TimeDisplayMode,
     base: &Path,
+    path_display: PathDisplay,
+    max_width: Option<usize>,
 ) -> Result<(String, String)> {
-    let mut displayed_path = utils::path::display(item.original_path().strip_prefix(base).unwrap());
+    let mut displayed_path = match path_display {
+        PathDisplay::Decoded => {
+            utils::path::display(item.original_path().strip_prefix(base).unwrap())
+        }
+        PathDisplay::Raw => raw_path(item)?,
+        PathDisplay::Tilde => trash_info::tilde_path(
+            &item.original_path(),
+            env::var_os("HOME").as_deref().map(Path::new),
+        ),
+    };
+    // shorten before coloring, so that no escape sequence is cut
+    if let Some(max_width) = max_width {
+        displayed_path = utils::path::shorten_path(&displayed_path, max_width);
+    }
+    let mut displayed_date = display_item_date(item, time_display_mode);
+    if color {
+        displayed_date = DATE_COLOUR.paint(displayed_date).to_string();
+    }
     if cfg!(target_os = "linux") && color {
         if let Some(style) = item_lscolors(item)? {
             let ansi_style = style.to_ansi_term_style();
             displayed_path = ansi_style.paint(displayed_path).to_string();
         }
     }
-    Ok((display_item_date(item, time_display_mode), displayed_path))
+    Ok((displayed_date, displayed_path))
+}
+
+/// Returns who or what trashed `item` as recorded by `trashy put --label`, or an empty string.
+fn deleted_by(item: &TrashItem) -> String {
+    if !cfg!(target_os = "linux") {
+        return String::new();
+    }
+    trash_dir::read_info(Path::new(&item.id))
+        .ok()
+        .and_then(|info| Some(info.deleted_by()?.to_owned()))
+        .unwrap_or_default()
+}
+
+/// Returns the namespace `item` was put into with `trashy put --namespace`, if any.
+fn namespace(item: &TrashItem) -> Option<String> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    Some(trash_dir::read_info(Path::new(&item.id)).ok()?.namespace()?.to_owned())
+}
+
+/// Returns the `Path=` value of the info file of `item`, as it was written.
+fn raw_path(item: &TrashItem) -> Result<String> {
+    if !cfg!(target_os = "linux") {
+        bail!("Raw paths are only available on linux");
+    }
+    Ok(trash_dir::read_info(Path::new(&item.id))?.path().to_string())
+}
+
+/// The colour of deletion dates, so that they stand apart from the paths coloured by `LS_COLORS`.
+const DATE_COLOUR: ansi_term::Colour = ansi_term::Colour::Blue;
+
+/// The width that long paths are never truncated below, even in a narrow terminal.
+const MIN_PATH_WIDTH: usize = 20;
+
+/// Which optional columns the table of items has, and how wide it may get.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TableColumns {
+    pub show_size: bool,
+    pub show_source: bool,
+    /// Long paths are truncated to fit into this many columns
+    pub terminal_width: Option<usize>,
 }
 
 pub struct TrashItemDisplay {
     i: u32,
     time: String,
     path: String,
+    size: String,
+    trash: String,
+    source: String,
+    in_home_trash: bool,
 }
 
 impl Tabled for TrashItemDisplay {
-    const LENGTH: usize = 3;
+    const LENGTH: usize = 6;
 
     fn fields(&self) -> Vec<Cow<'_, str>> {
-        vec![self.i.to_string().into(), self.time.clone().into(), self.path.clone().into()]
+        vec![
+            self.i.to_string().into(),
+            self.time.clone().into(),
+            self.path.clone().into(),
+            self.size.clone().into(),
+            self.trash.clone().into(),
+            self.source.clone().into(),
+        ]
     }
 
     fn headers() -> Vec<Cow<'static, str>> {
-        vec!["i".into(), "Time".into(), "Path".into()]
+        vec![
+            "i".into(),
+            "Time".into(),
+            "Path".into(),
+            "Size".into(),
+            "Trash".into(),
+            "Source".into(),
+        ]
     }
 }
 
@@ -271,14 +881,218 @@ pub fn item_lscolors(item: &TrashItem) -> Result<Option<lscolors::Style>> {
     }
 }
 
+/// Returns the `n` largest of `items`, largest first, without sorting all of them.
+///
+/// Items of the same size are sorted newest first.
+pub fn largest(items: Vec<TrashItem>, n: usize) -> Vec<TrashItem> {
+    // a min-heap of the largest items seen so far
+    let mut heap = BinaryHeap::with_capacity(n + 1);
+    for (index, item) in items.iter().enumerate() {
+        heap.push(cmp::Reverse((item_size(item).unwrap_or(0), item.time_deleted, index)));
+        if heap.len() > n {
+            heap.pop();
+        }
+    }
+    let mut items: Vec<_> = items.into_iter().map(Some).collect();
+    heap.into_sorted_vec()
+        .into_iter()
+        .filter_map(|cmp::Reverse((_, _, index))| items[index].take())
+        .collect()
+}
+
+fn display_stats(items: &[TrashItem], config_args: &app::ConfigArgs) -> Result<()> {
+    let mut stdout = io::stdout().lock();
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            writeln!(stdout)?;
+        }
+        let stat = stat(item)?;
+        let size = stat.size.map_or_else(|| "unknown".into(), utils::human_size);
+        writeln!(stdout, "Path:     {}", utils::path::display(&item.original_path()))?;
+        writeln!(stdout, "Name:     {}", utils::path::display(&stat.trashed_name))?;
+        writeln!(stdout, "Deleted:  {}", display_item_date(item, config_args.time_display_mode))?;
+        writeln!(stdout, "Size:     {size}")?;
+        writeln!(stdout, "Type:     {}", stat.file_type)?;
+        writeln!(stdout, "Intact:   {}", if stat.intact { "yes" } else { "no" })?;
+        let restore = if stat.crosses_filesystems { "across filesystems" } else { "rename" };
+        writeln!(stdout, "Restore:  {restore}")?;
+    }
+    Ok(())
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManifestFormat {
+    Csv,
+    Json,
+}
+
+/// Writes the metadata of `items` to `writer`, one record per item.
+pub fn export_manifest(
+    items: &[TrashItem],
+    mut writer: impl Write,
+    format: ManifestFormat,
+) -> Result<()> {
+    let records = items.iter().map(|item| -> Result<_> {
+        let stat = stat(item)?;
+        let deletion_date = Local.timestamp_opt(item.time_deleted, 0).unwrap().to_rfc3339();
+        Ok((
+            utils::path::display(&item.original_path()),
+            utils::path::display(&stat.trashed_name),
+            deletion_date,
+            stat.size,
+        ))
+    });
+    match format {
+        ManifestFormat::Csv => {
+            writeln!(writer, "original_path,trashed_name,deletion_date,size")?;
+            for record in records {
+                let (path, name, date, size) = record?;
+                let size = size.map_or_else(String::new, |size| size.to_string());
+                writeln!(writer, "{},{},{date},{size}", csv_field(&path), csv_field(&name))?;
+            }
+        }
+        ManifestFormat::Json => {
+            write!(writer, "[")?;
+            for (i, record) in records.enumerate() {
+                let (path, name, date, size) = record?;
+                let size = size.map_or_else(|| "null".into(), |size| size.to_string());
+                let separator = if i == 0 { "" } else { "," };
+                write!(
+                    writer,
+                    "{separator}\n  {{\"original_path\": {}, \"trashed_name\": {}, \
+                     \"deletion_date\": \"{date}\", \"size\": {size}}}",
+                    json_string(&path),
+                    json_string(&name)
+                )?;
+            }
+            writeln!(writer, "\n]")?;
+        }
+    }
+    Ok(())
+}
+
+fn csv_field(s: &str) -> Cow<'_, str> {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\"")).into()
+    } else {
+        s.into()
+    }
+}
+
+fn json_string(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// The details of a trashed item that are not in its info file.
+pub struct EntryStat {
+    /// The name of the file inside the trash
+    pub trashed_name: PathBuf,
+    pub size: Option<u64>,
+    pub file_type: &'static str,
+    /// Whether both the trashed file and its info file exist
+    pub intact: bool,
+    /// Whether restoring would move the file to another filesystem instead of renaming it
+    pub crosses_filesystems: bool,
+}
+
+/// Collects the details of `item`, reading the metadata of the trashed file only once.
+pub fn stat(item: &TrashItem) -> Result<EntryStat> {
+    if !cfg!(target_os = "linux") {
+        bail!("Showing the details of items is only supported on linux");
+    }
+    let info_path = Path::new(&item.id);
+    let files_path = files_path_from_info_path(info_path);
+    let trashed_name = PathBuf::from(files_path.file_name().context("Invalid info path")?);
+    let crosses_filesystems = !trash_dir::same_filesystem(&files_path, &item.original_parent);
+    let meta = match fs::symlink_metadata(&files_path) {
+        Ok(meta) => meta,
+        Err(_) => {
+            return Ok(EntryStat {
+                trashed_name,
+                size: None,
+                file_type: "missing",
+                intact: false,
+                crosses_filesystems,
+            })
+        }
+    };
+    let file_type = file_type_name(meta.file_type());
+    let size = if meta.is_dir() { item_size(item) } else { Some(meta.len()) };
+    Ok(EntryStat {
+        trashed_name,
+        size,
+        file_type,
+        intact: info_path.is_file(),
+        crosses_filesystems,
+    })
+}
+
+/// The name `trashy list --stat` shows for a type of file.
+pub fn file_type_name(file_type: fs::FileType) -> &'static str {
+    if file_type.is_dir() {
+        "directory"
+    } else if file_type.is_symlink() {
+        "symlink"
+    } else if file_type.is_file() {
+        "file"
+    } else {
+        "other"
+    }
+}
+
+/// Opens the trashed file of `item` for reading.
+pub fn open_reader(item: &TrashItem) -> Result<fs::File> {
+    if !cfg!(target_os = "linux") {
+        bail!("Reading trashed files is only supported on linux");
+    }
+    let files_path = files_path_from_info_path(Path::new(&item.id));
+    if files_path.is_dir() {
+        bail!("{} is a directory", utils::path::display(&item.original_path()));
+    }
+    fs::File::open(&files_path).with_context(|| format!("Failed to open {}", files_path.display()))
+}
+
+/// Returns the size of the trashed file or directory backing `item`.
+///
+/// The sizes of directories are read from the `directorysizes` cache when it is current. This is
+/// `None` when the trashed file can't be read, and on every platform but linux.
+pub fn item_size(item: &TrashItem) -> Option<u64> {
+    if !cfg!(target_os = "linux") {
+        return None;
+    }
+    let info_path = Path::new(&item.id);
+    let files_path = files_path_from_info_path(info_path);
+    if files_path.symlink_metadata().ok()?.is_dir() {
+        let cached = trash_item::info_mtime(item)
+            .ok()
+            .and_then(|mtime| trash_dir::cached_directory_size(info_path, mtime));
+        if cached.is_some() {
+            return cached;
+        }
+    }
+    utils::path::size(&files_path).ok()
+}
+
 pub fn display_item_date(item: &TrashItem, time_display_mode: TimeDisplayMode) -> String {
-    let datetime = Local.timestamp(item.time_deleted, 0);
+    let datetime = Local.timestamp_opt(item.time_deleted, 0).unwrap();
     match time_display_mode {
         TimeDisplayMode::Precise => {
-            format!("{}", Local.timestamp(item.time_deleted, 0).format("%d/%m/%Y %H:%M"))
+            format!("{}", datetime.format("%d/%m/%Y %H:%M"))
         }
         TimeDisplayMode::Imprecise => {
-            let humantime = chrono_humanize::HumanTime::from(datetime);
+            let humantime = chrono_humanize::HumanTime::from(-trash_item::age(item, &SystemClock));
             format!("{humantime}")
         }
     }
@@ -291,3 +1105,125 @@ pub fn files_path_from_info_path(info_path: &Path) -> PathBuf {
     files_path.push(file_name);
     files_path
 }
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use super::super::utils::use_home_trash_in;
+    use super::*;
+
+    #[test]
+    fn table_columns() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::write(dir.path().join("files/small"), "abc").unwrap();
+        fs::write(dir.path().join("files/large"), vec![0; 2048]).unwrap();
+        let item = |name: &str, original_parent: &str| TrashItem {
+            id: dir.path().join(format!("info/{name}.trashinfo")).into(),
+            name: name.into(),
+            original_parent: original_parent.into(),
+            time_deleted: 0,
+        };
+        let long_parent = format!("/{}", "long".repeat(20));
+        let items = [item("small", "/a"), item("large", &long_parent)];
+        let table = indexed_items_to_table(
+            items.iter().zip(0..2).map(swap),
+            false,
+            false,
+            TimeDisplayMode::Precise,
+            Path::new(""),
+            PathDisplay::Decoded,
+            TableColumns { show_size: true, show_source: false, terminal_width: Some(60) },
+        )
+        .unwrap()
+        .to_string();
+        let lines: Vec<_> = table.lines().map(str::trim_end).collect();
+        assert_eq!(lines.len(), 2, "{table}");
+        // the sizes are aligned to the right
+        let size_end = |line: &str, size: &str| line.find(size).map(|i| line[..i].chars().count());
+        assert_eq!(size_end(lines[0], "2.0 KiB "), size_end(lines[1], "    3 B "), "{table}");
+        // the long path is shortened in the middle
+        assert!(lines[0].contains(&format!("/{}…/large", "long".repeat(3))), "{table}");
+        assert!(lines[1].contains("/a/small"), "{table}");
+    }
+
+    #[test]
+    fn process_items_breaks_ties_by_trashed_name() {
+        let item = |name: &str, time_deleted| TrashItem {
+            id: format!("/trash/info/{name}.trashinfo").into(),
+            name: name.into(),
+            original_parent: "/a".into(),
+            time_deleted,
+        };
+        let names =
+            |items: Vec<TrashItem>| items.into_iter().map(|item| item.name).collect::<Vec<_>>();
+        let items = vec![item("c", 1), item("b", 2), item("d", 1), item("a", 1)];
+        let mut reversed = items.clone();
+        reversed.reverse();
+        for items in [items, reversed] {
+            assert_eq!(
+                names(process_items(false, None, Filters(Vec::new()), items.clone())),
+                ["b", "a", "c", "d"]
+            );
+            assert_eq!(
+                names(process_items(true, None, Filters(Vec::new()), items)),
+                ["d", "c", "a", "b"]
+            );
+        }
+    }
+
+    #[test]
+    fn apply_config_rev() {
+        use clap::{CommandFactory, FromArgMatches};
+
+        let config = app::config::Config { rev: Some(true), ..Default::default() };
+        for (args, rev) in [
+            (&[][..], true),
+            (&["--no-rev"], false),
+            (&["--rev"], true),
+            (&["--rev", "--no-rev"], false),
+        ] {
+            let matches =
+                QueryArgs::command().no_binary_name(true).get_matches_from(args.iter().copied());
+            let mut query_args = QueryArgs::from_arg_matches(&matches).unwrap();
+            query_args.apply_config(&config, &matches);
+            assert_eq!(query_args.rev, rev, "{args:?}");
+        }
+    }
+
+    #[test]
+    fn list_since_with() {
+        use crate::{clock::FixedClock, trash_info::TrashInfo};
+
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        trash_dir::create_home_trash(trash_dir::DirMode::default()).unwrap();
+        let trash = TrashDir::home().unwrap();
+        let clock = FixedClock(Local.with_ymd_and_hms(2023, 6, 1, 12, 0, 0).unwrap());
+        for (name, minutes_ago) in [("recent", 30), ("old", 90)] {
+            fs::write(trash.path().join("files").join(name), "").unwrap();
+            let date = clock.0.naive_local() - chrono::Duration::minutes(minutes_ago);
+            let info = TrashInfo::new(&dir.path().join(name), date);
+            let info_path = trash.path().join(format!("info/{name}.trashinfo"));
+            trash_dir::write_info(&info_path, &info).unwrap();
+        }
+
+        let query_args = QueryArgs::parse_from(["trashy"]);
+        let items = query_args.list_since_with(&clock, Duration::from_secs(3600)).unwrap();
+        let names: Vec<_> = items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["recent"]);
+    }
+
+    #[test]
+    fn list_missing_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        assert!(list_only(Scope::Home).unwrap().is_empty());
+        assert!(is_empty(Scope::Home).unwrap());
+        assert!(list_orphans(Scope::Home).unwrap().is_empty());
+        assert!(read_scoped(Scope::Home).unwrap().1.is_empty());
+        // the trash directories of other drives depend on the machine, so only check that
+        // reading them doesn't create the home trash
+        list_only(Scope::AllMounts).unwrap();
+        assert_eq!(fs::read_dir(dir.path()).unwrap().count(), 0);
+    }
+}
@@ -0,0 +1,39 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use crate::{
+    print,
+    trash_dir::{self, DirMode},
+    utils,
+};
+
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// The new trash directory, like '/mnt/big/Trash'
+    ///
+    /// Afterwards, point $XDG_DATA_HOME to its parent so that it is used as the home trash.
+    #[arg(verbatim_doc_comment)]
+    new_root: PathBuf,
+}
+
+impl Args {
+    pub fn run(&self) -> Result<()> {
+        if !cfg!(target_os = "linux") {
+            bail!("`trashy migrate` is only supported on linux");
+        }
+        let report = trash_dir::migrate_home_trash(&self.new_root, DirMode::from_env()?)?;
+        let plural = if report.moved == 1 { "" } else { "s" };
+        println!("{} item{plural} moved to {}", report.moved, utils::path::display(&self.new_root));
+        for entry in &report.failed {
+            print::err_display(format!("{}: {:#}", entry.info_path.display(), entry.error));
+        }
+        if !report.failed.is_empty() {
+            let failed = report.failed.len();
+            let plural = if failed == 1 { "" } else { "s" };
+            bail!("{failed} item{plural} could not be moved");
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,64 @@
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use crate::{
+    print,
+    trash_dir::{self, ImportReport},
+    utils,
+};
+
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// Only show the out of date entries of the cache
+    ///
+    /// Prints the trashed directories whose cached size or modification time no longer matches,
+    /// for example because they were changed by another program. Nothing is rebuilt.
+    #[arg(long)]
+    check: bool,
+
+    /// Also move items that other tools left in the root of a trash directory
+    ///
+    /// Some tools put info files and trashed files directly into the trash directory
+    /// instead of its 'info' and 'files' directories. These are moved to where they belong,
+    /// adding a number to names that are taken.
+    #[arg(long, conflicts_with = "check", verbatim_doc_comment)]
+    import_legacy: bool,
+}
+
+impl Args {
+    /// Rebuilds the `directorysizes` cache of every trash directory, or checks it with `--check`.
+    pub fn run(&self) -> Result<()> {
+        if !cfg!(target_os = "linux") {
+            bail!("`trashy optimize` is only supported on linux");
+        }
+        for trash_dir in trash_dir::all_trash_dirs() {
+            if self.check {
+                for path in trash_dir.stale_cache_entries()? {
+                    println!("{}", utils::path::display(&path));
+                }
+            } else {
+                if self.import_legacy {
+                    print_import_report(&trash_dir.import_legacy()?);
+                }
+                trash_dir.rebuild_directorysizes()?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn print_import_report(report: &ImportReport) {
+    for (old, new) in &report.renamed {
+        println!("{} was imported as {}, since its name was taken", old.display(), new.display());
+    }
+    for path in &report.orphaned {
+        println!("{} has no info file, so it was imported as an orphan", path.display());
+    }
+    for entry in &report.failed {
+        print::err_display(format!("{}: {:#}", entry.info_path.display(), entry.error));
+    }
+    if report.imported > 0 {
+        let plural = if report.imported == 1 { "" } else { "s" };
+        println!("{} item{plural} imported", report.imported);
+    }
+}
@@ -1,22 +1,825 @@
-use std::path::PathBuf;
+use std::{
+    collections::{HashMap, HashSet},
+    error, fmt, fs, io,
+    path::{Path, PathBuf},
+};
 
-use anyhow::{bail, Result};
-use clap::Parser;
+use anyhow::{bail, Context, Result};
+use chrono::NaiveDateTime;
+use clap::{ArgAction, Parser, ValueEnum};
+use trash::TrashItem;
 
-use crate::app;
+use crate::{
+    app,
+    clock::{Clock, SystemClock},
+    print,
+    summary::{Operation, OperationSummary},
+    trash_dir::{self, DirMode, TrashDir},
+    trash_info::{self, TrashInfo},
+    undo::UndoToken,
+    utils,
+};
 
-#[derive(Parser, Debug, PartialEq)]
+use super::list;
+
+#[derive(Parser, Debug, Clone, PartialEq)]
 pub struct PutArgs {
     /// The paths to put into the trash.
     pub paths: Vec<PathBuf>,
+
+    /// Record a different deletion date
+    ///
+    /// Instead of the current time, the paths are recorded as trashed at this date.
+    /// This is useful when recreating a trash from backups.
+    /// Examples:
+    ///     --deletion-date 2020-01-01T00:00:00
+    #[arg(
+        long,
+        value_name = "DATE",
+        value_parser = trash_info::parse_trash_datetime,
+        verbatim_doc_comment
+    )]
+    pub deletion_date: Option<NaiveDateTime>,
+
+    /// Record who or what trashed the paths
+    ///
+    /// The label is stored with every trashed path, and 'trashy list --show-source' shows it.
+    /// This helps to tell apart files trashed by scripts in a shared environment.
+    /// Examples:
+    ///     --label nightly-cleanup
+    #[arg(long, value_name = "LABEL", verbatim_doc_comment)]
+    pub label: Option<String>,
+
+    /// Put the paths into a namespace, like the name of the application trashing them
+    ///
+    /// The namespace is stored with every trashed path, and 'trashy list --namespace' only shows
+    /// the items of one namespace. The items stay in the same trash directory as all others,
+    /// so their names inside the trash are unique across the whole trash, not per namespace:
+    /// trashing 'a.txt' into two namespaces stores it as 'a.txt' and 'a.txt.2'.
+    /// Examples:
+    ///     --namespace my-editor
+    #[arg(long, value_name = "NS", verbatim_doc_comment)]
+    pub namespace: Option<String>,
+
+    /// Record a checksum of every trashed file to verify it when it is restored
+    ///
+    /// The SHA-256 of the contents of every regular file is stored with it, and
+    /// 'trashy restore' fails when the restored file does not match it anymore.
+    /// This guards against corruption, like when a file is copied into the trash of another drive.
+    /// Reading every file takes time, so this is off by default. Directories get no checksum.
+    #[arg(long, verbatim_doc_comment)]
+    pub checksum: bool,
+
+    /// Print a token for every trashed path that undoes trashing it
+    ///
+    /// Passing a token to 'trashy restore --undo' restores exactly the entry that was trashed,
+    /// even if more files with the same path were trashed since.
+    /// A token can only be used once.
+    #[arg(long, verbatim_doc_comment)]
+    pub undo_token: bool,
+
+    /// Use this name for the trashed file instead of one derived from the path
+    ///
+    /// The name is used as it is when it is free in the trash directory,
+    /// otherwise a number is appended to it, like 'name.2'.
+    /// This can only be used when putting a single path.
+    #[arg(long, value_name = "NAME", value_parser = parse_trashed_name, verbatim_doc_comment)]
+    pub trashed_name: Option<String>,
+
+    /// Put what is inside the given directories instead of the directories themselves
+    ///
+    /// Every file and directory directly inside is put as its own item with its own original path,
+    /// and the directories are left in place, now empty. This is useful to clear '~/Downloads'.
+    /// An item that can't be put is reported and does not stop the others.
+    #[arg(long, conflicts_with = "trashed_name", verbatim_doc_comment)]
+    pub contents: bool,
+
+    /// Refuse to put directories that are not empty
+    ///
+    /// A directory is always moved into the trash as a whole, together with everything inside it.
+    /// With this option only empty directories are put, like 'rm' without '-r'.
+    #[arg(long = "no-recursive", action = ArgAction::SetFalse, verbatim_doc_comment)]
+    pub recursive: bool,
+
+    /// Refuse to put paths whose absolute path is longer than this many bytes
+    ///
+    /// Restoring an item fails when its original path is too long for the filesystem,
+    /// so this catches such paths before they are trashed.
+    #[arg(long, value_name = "BYTES", verbatim_doc_comment)]
+    pub max_path_len: Option<usize>,
+
+    /// Copy paths into the home trash when the trash of their drive can't be used
+    ///
+    /// Paths on another drive than the home directory are put into the trash of that drive.
+    /// When that trash can't be created or written to, like on a drive mounted by another user,
+    /// the path is copied into the home trash and then removed instead of failing.
+    /// Copying is slower and does not keep ownership. Extended attributes are kept on linux
+    /// when trashy is built with the 'xattr' feature, which is on by default.
+    #[arg(long, verbatim_doc_comment)]
+    pub fallback_to_home: bool,
+
+    /// How paths are put into the trash
+    ///
+    /// Declare how the trashed file is created:
+    ///    'move':         move the path into the trash
+    ///    'hardlink':     hardlink the file into the trash, then unlink the original
+    /// Hardlinking only works for regular files on the same filesystem as the home trash.
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = LinkMode::Move,
+        conflicts_with = "fallback_to_home",
+        verbatim_doc_comment
+    )]
+    pub link_mode: LinkMode,
+
+    #[clap(flatten)]
+    pub quota: TrashQuota,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LinkMode {
+    Move,
+    Hardlink,
+}
+
+/// A path can't be put with `LinkMode::Hardlink`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CannotHardlink {
+    pub path: PathBuf,
+    pub reason: &'static str,
+}
+
+impl fmt::Display for CannotHardlink {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot hardlink '{}' into the trash because {}",
+            self.path.display(),
+            self.reason
+        )
+    }
+}
+
+impl error::Error for CannotHardlink {}
+
+/// A path is on a drive that is mounted read-only, so it can't be trashed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ReadOnlyMedia {
+    pub mount: PathBuf,
+}
+
+impl fmt::Display for ReadOnlyMedia {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot trash files on '{}' because it is mounted read-only. Remount it read-write \
+             to trash them",
+            self.mount.display()
+        )
+    }
+}
+
+impl error::Error for ReadOnlyMedia {}
+
+/// Fails before anything is trashed if any of `paths` is on a drive that is mounted read-only.
+fn check_not_read_only(paths: &[PathBuf]) -> Result<(), ReadOnlyMedia> {
+    match paths.iter().find_map(|path| trash_dir::read_only_mount(path)) {
+        Some(mount) => Err(ReadOnlyMedia { mount }),
+        None => Ok(()),
+    }
+}
+
+/// Fails if any of `paths` is not a regular file on the same filesystem as `trash`.
+fn check_hardlinkable(paths: &[PathBuf], trash: &Path) -> Result<(), CannotHardlink> {
+    for path in paths {
+        let reason = match fs::symlink_metadata(path) {
+            Err(_) => Some("it does not exist"),
+            Ok(meta) if meta.is_dir() => Some("it is a directory"),
+            Ok(meta) if !meta.is_file() => Some("it is not a regular file"),
+            Ok(_) if !trash_dir::same_filesystem(path, trash) => {
+                Some("it is on another filesystem than the home trash")
+            }
+            Ok(_) => None,
+        };
+        if let Some(reason) = reason {
+            return Err(CannotHardlink { path: path.clone(), reason });
+        }
+    }
+    Ok(())
+}
+
+/// The absolute path of a path to put is longer than `max_path_len`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathTooLong {
+    pub path: PathBuf,
+    pub len: usize,
+    pub max: usize,
+}
+
+impl fmt::Display for PathTooLong {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot put '{}' because its path is {} bytes long, which is more than {}",
+            self.path.display(),
+            self.len,
+            self.max
+        )
+    }
+}
+
+impl error::Error for PathTooLong {}
+
+fn check_path_len(paths: &[PathBuf], max: usize) -> Result<(), PathTooLong> {
+    for path in paths {
+        let absolute =
+            utils::path::canonicalize_parent(path).unwrap_or_else(|| utils::path::normalize(path));
+        let len = absolute.as_os_str().len();
+        if len > max {
+            return Err(PathTooLong { path: path.clone(), len, max });
+        }
+    }
+    Ok(())
+}
+
+/// A directory that is not empty was put without allowing it with `recursive`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirectoryNotEmpty {
+    pub path: PathBuf,
+}
+
+impl fmt::Display for DirectoryNotEmpty {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot put '{}' because it is a directory that is not empty",
+            self.path.display()
+        )
+    }
+}
+
+impl error::Error for DirectoryNotEmpty {}
+
+/// Fails if any of `paths` is a directory with something inside it, without following symlinks.
+fn check_not_recursive(paths: &[PathBuf]) -> Result<(), DirectoryNotEmpty> {
+    for path in paths {
+        let is_dir = fs::symlink_metadata(path).map_or(false, |meta| meta.is_dir());
+        if is_dir && fs::read_dir(path).map_or(false, |mut entries| entries.next().is_some()) {
+            return Err(DirectoryNotEmpty { path: path.clone() });
+        }
+    }
+    Ok(())
+}
+
+fn parse_trashed_name(s: &str) -> Result<String, String> {
+    if s.is_empty() || s == "." || s == ".." {
+        return Err(format!("`{s}` is not a file name"));
+    }
+    if s.contains(['/', '\0']) {
+        return Err("The name must not contain path separators".into());
+    }
+    Ok(s.to_string())
 }
 
 impl PutArgs {
-    pub fn run(&self, _: &app::ConfigArgs) -> Result<()> {
+    pub fn run(&self, config_args: &app::ConfigArgs) -> Result<()> {
+        if !self.contents {
+            return self.put(config_args, config_args.on_complete());
+        }
+        if self.paths.is_empty() {
+            bail!("No directories were specified to put the contents of");
+        }
+        let mut failed = 0;
+        for dir in &self.paths {
+            let report = self.put_contents(dir, config_args, config_args.on_complete())?;
+            for (path, e) in &report.failed {
+                print::err_display(format!("Failed to put {}: {e:#}", utils::path::display(path)));
+            }
+            if !report.failed.is_empty() {
+                let total = report.trashed.len() + report.failed.len();
+                print::err_display(format!(
+                    "{} of {total} items in {} could not be put",
+                    report.failed.len(),
+                    utils::path::display(dir)
+                ));
+            }
+            failed += report.failed.len();
+        }
+        if failed > 0 {
+            let plural = if failed == 1 { "" } else { "s" };
+            bail!("{failed} item{plural} could not be put");
+        }
+        Ok(())
+    }
+
+    /// Puts everything directly inside `dir` into the trash, each as its own item, and keeps
+    /// `dir` itself.
+    ///
+    /// The children are put in the order of their names. One that can't be put does not stop the
+    /// others, it is returned in the report instead.
+    fn put_contents(
+        &self,
+        dir: &Path,
+        config_args: &app::ConfigArgs,
+        on_complete: Option<impl FnOnce(OperationSummary)>,
+    ) -> Result<PutReport> {
+        let mut children = fs::read_dir(dir)
+            .and_then(|read_dir| {
+                read_dir.map(|entry| Ok(entry?.path())).collect::<io::Result<Vec<_>>>()
+            })
+            .with_context(|| {
+                format!("Failed to read the contents of {}", utils::path::display(dir))
+            })?;
+        children.sort();
+        let mut report = PutReport::default();
+        let mut sizes = Vec::new();
+        for child in children {
+            let size = on_complete.is_some().then(|| utils::path::size(&child).ok()).flatten();
+            let args = PutArgs { paths: vec![child.clone()], ..self.clone() };
+            match args.put_all(config_args) {
+                Ok(()) => {
+                    report.trashed.push(child);
+                    sizes.push(size);
+                }
+                Err(e) => report.failed.push((child, e)),
+            }
+        }
+        if let Some(on_complete) = on_complete {
+            on_complete(OperationSummary::new(Operation::Put, sizes));
+        }
+        Ok(report)
+    }
+
+    /// Puts the paths into the trash, then passes what was trashed to `on_complete`.
+    fn put(
+        &self,
+        config_args: &app::ConfigArgs,
+        on_complete: Option<impl FnOnce(OperationSummary)>,
+    ) -> Result<()> {
+        // the sizes can't be read from the original paths once they are trashed
+        let sizes: Vec<_> = match on_complete {
+            Some(_) => self.paths.iter().map(|path| utils::path::size(path).ok()).collect(),
+            None => Vec::new(),
+        };
+        self.put_all(config_args)?;
+        if let Some(on_complete) = on_complete {
+            on_complete(OperationSummary::new(Operation::Put, sizes));
+        }
+        Ok(())
+    }
+
+    fn put_all(&self, config_args: &app::ConfigArgs) -> Result<()> {
         let paths = &self.paths;
         if paths.is_empty() {
             bail!("No paths were specified to trash");
         }
-        Ok(trash::delete_all(paths)?)
+        if !self.recursive {
+            check_not_recursive(paths)?;
+        }
+        if let Some(max) = self.max_path_len {
+            check_path_len(paths, max)?;
+        }
+        // the quota may come from the config file, so this is only known after merging it
+        if self.quota.policy.is_some() && self.quota.max_bytes.is_none() {
+            bail!(
+                "A quota policy needs a quota, set one with --max-trash-size or max-trash-size in \
+                 the config file"
+            );
+        }
+        if self.deletion_date.is_some() && !cfg!(target_os = "linux") {
+            bail!("--deletion-date is only supported on linux");
+        }
+        if self.label.is_some() && !cfg!(target_os = "linux") {
+            bail!("--label is only supported on linux");
+        }
+        if self.namespace.is_some() && !cfg!(target_os = "linux") {
+            bail!("--namespace is only supported on linux");
+        }
+        if self.checksum && !cfg!(target_os = "linux") {
+            bail!("--checksum is only supported on linux");
+        }
+        if self.undo_token && !cfg!(target_os = "linux") {
+            bail!("--undo-token is only supported on linux");
+        }
+        if self.trashed_name.is_some() {
+            if !cfg!(target_os = "linux") {
+                bail!("--trashed-name is only supported on linux");
+            }
+            if paths.len() != 1 {
+                bail!("--trashed-name can only be used when putting a single path");
+            }
+        }
+        if cfg!(target_os = "linux") {
+            check_not_read_only(paths)?;
+        }
+        if self.fallback_to_home && !cfg!(target_os = "linux") {
+            bail!("--fallback-to-home is only supported on linux");
+        }
+        let hardlink = self.link_mode == LinkMode::Hardlink;
+        if hardlink {
+            if !cfg!(target_os = "linux") {
+                bail!("--link-mode hardlink is only supported on linux");
+            }
+            let home_trash = trash_dir::home_trash().context("There is no home trash")?;
+            check_hardlinkable(paths, &home_trash)?;
+        }
+        let (copied, paths): (Vec<_>, Vec<_>) =
+            paths.iter().partition(|path| self.fallback_to_home && needs_fallback(path));
+        if cfg!(target_os = "linux") {
+            if let Some(home_trash) = trash_dir::home_trash() {
+                let to_home_trash: Vec<_> = paths
+                    .iter()
+                    .copied()
+                    .filter(|path| trash_dir::same_filesystem(path, &home_trash))
+                    .chain(copied.iter().copied())
+                    .collect();
+                if !to_home_trash.is_empty() {
+                    trash_dir::create_home_trash(DirMode::from_env()?)?;
+                    trash_dir::check_writable(&home_trash)?;
+                    let purged = self.quota.make_room(&to_home_trash)?;
+                    if !purged.is_empty() {
+                        let len = purged.len();
+                        let (plural, verb) = if len == 1 { ("", "was") } else { ("s", "were") };
+                        println!("{len} item{plural} {verb} emptied to stay under the trash quota");
+                        list::display_items(&purged, config_args)?;
+                    }
+                }
+            }
+        }
+        if self.deletion_date.is_none()
+            && self.label.is_none()
+            && self.namespace.is_none()
+            && !self.checksum
+            && !self.undo_token
+            && self.trashed_name.is_none()
+            && copied.is_empty()
+            && !hardlink
+        {
+            return Ok(trash::delete_all(paths)?);
+        }
+        let trashed: HashSet<_> = paths
+            .iter()
+            .chain(&copied)
+            .filter_map(|path| utils::path::canonicalize_parent(path))
+            .collect();
+        // the originals are read before anything can happen to them
+        let checksums = match self.checksum {
+            true => checksums(paths.iter().chain(&copied).copied())?,
+            false => HashMap::new(),
+        };
+        let info_paths_before: HashSet<_> =
+            trash_dir::all_trash_dirs().iter().flat_map(TrashDir::info_paths).collect();
+        if hardlink {
+            let home = TrashDir::home().context("There is no home trash")?;
+            let now = SystemClock.now_local().naive_local();
+            for path in &paths {
+                home.put_by_hardlink(path, now)?;
+            }
+        } else if !paths.is_empty() {
+            trash::delete_all(&paths)?;
+        }
+        if !copied.is_empty() {
+            let home = TrashDir::home().context("There is no home trash to fall back to")?;
+            let now = SystemClock.now_local().naive_local();
+            for path in copied {
+                home.put_by_copy(path, now)?;
+                print::warn_display(format!(
+                    "{} was copied into the home trash across filesystems, since the trash of its \
+                     drive can't be used",
+                    utils::path::display(path)
+                ));
+            }
+        }
+        let mut entries = new_entries(&trashed, &info_paths_before);
+        if let Some(name) = &self.trashed_name {
+            let (trash_dir, info_path, _) = entries
+                .first_mut()
+                .context("Could not find the info file of the trashed path to rename it")?;
+            *info_path = trash_dir.rename_entry(info_path, name)?;
+        }
+        if self.deletion_date.is_some()
+            || self.label.is_some()
+            || self.namespace.is_some()
+            || self.checksum
+        {
+            for (trash_dir, info_path, info) in &entries {
+                let mut info = info.clone();
+                if let Some(deletion_date) = self.deletion_date {
+                    info = info.with_deletion_date(deletion_date);
+                }
+                if let Some(label) = &self.label {
+                    info = info.with_deleted_by(label.as_str());
+                }
+                if let Some(namespace) = &self.namespace {
+                    info = info.with_namespace(namespace.as_str());
+                }
+                if let Some(checksum) = checksums.get(&trash_dir.original_path(&info)) {
+                    info = info.with_sha256(checksum);
+                }
+                trash_dir::write_info(info_path, &info)?;
+            }
+        }
+        if self.undo_token {
+            for (trash_dir, info_path, _) in entries {
+                println!("{}", UndoToken::new(trash_dir, info_path));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// The children of a directory that `PutArgs::put_contents` put, and the ones it could not put.
+#[derive(Debug, Default)]
+struct PutReport {
+    trashed: Vec<PathBuf>,
+    failed: Vec<(PathBuf, anyhow::Error)>,
+}
+
+/// Returns the SHA-256 of every regular file of `paths` by the original path it is trashed with.
+fn checksums<'a>(paths: impl Iterator<Item = &'a PathBuf>) -> Result<HashMap<PathBuf, String>> {
+    let mut checksums = HashMap::new();
+    for path in paths {
+        if !fs::symlink_metadata(path).map_or(false, |meta| meta.is_file()) {
+            continue;
+        }
+        let checksum = utils::path::sha256(path)
+            .with_context(|| format!("Failed to read {} to record its checksum", path.display()))?;
+        if let Some(original_path) = utils::path::canonicalize_parent(path) {
+            checksums.insert(original_path, checksum);
+        }
+    }
+    Ok(checksums)
+}
+
+/// Whether `path` is on another drive whose trash directory can't be created or written to.
+fn needs_fallback(path: &Path) -> bool {
+    match trash_dir::trash_dir_for(path) {
+        Some((trash_dir::TrashDirRule::Home, _)) | None => false,
+        Some((_, trash)) => !trash_dir::is_usable(&trash),
+    }
+}
+
+/// Returns the info files of the `trashed` paths that are not in `info_paths_before`.
+fn new_entries(
+    trashed: &HashSet<PathBuf>,
+    info_paths_before: &HashSet<PathBuf>,
+) -> Vec<(TrashDir, PathBuf, TrashInfo)> {
+    let mut entries = Vec::new();
+    for trash_dir in trash_dir::all_trash_dirs() {
+        for info_path in trash_dir.info_paths() {
+            if info_paths_before.contains(&info_path) {
+                continue;
+            }
+            // skip info files that other programs wrote in the meantime
+            match trash_dir::read_info(&info_path) {
+                Ok(info) if trashed.contains(&trash_dir.original_path(&info)) => {
+                    entries.push((trash_dir.clone(), info_path, info));
+                }
+                _ => continue,
+            }
+        }
+    }
+    entries
+}
+
+#[derive(Parser, Debug, Clone, PartialEq)]
+pub struct TrashQuota {
+    /// Limit the size of the home trash
+    ///
+    /// The size can use the units K, M, G, and T, which are powers of 1024.
+    /// What happens when trashing would exceed the limit depends on '--quota-policy'.
+    /// Trashing a path that is larger than the limit on its own always fails.
+    /// Examples:
+    ///     --max-trash-size 1GiB
+    #[arg(
+        long = "max-trash-size",
+        value_name = "SIZE",
+        value_parser = utils::parse_size,
+        verbatim_doc_comment
+    )]
+    pub max_bytes: Option<u64>,
+
+    /// What to do when trashing would exceed '--max-trash-size'
+    ///
+    /// Declare how to stay under the limit:
+    ///    'reject':           do not trash anything, which is the default
+    ///    'purge-oldest':     permanently remove the oldest trashed items until there is room
+    /// The limit can also come from 'max-trash-size' in the config file.
+    #[arg(long = "quota-policy", value_enum, verbatim_doc_comment)]
+    pub policy: Option<QuotaPolicy>,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QuotaPolicy {
+    Reject,
+    PurgeOldest,
+}
+
+impl TrashQuota {
+    /// Makes room for `paths` in the home trash, returning the items that were purged.
+    ///
+    /// Nothing is checked or purged when no paths go into the home trash, even if it is over
+    /// the quota already.
+    fn make_room(&self, paths: &[&PathBuf]) -> Result<Vec<TrashItem>> {
+        let max = match self.max_bytes {
+            Some(max) if !paths.is_empty() => max,
+            _ => return Ok(Vec::new()),
+        };
+        let needed = paths.iter().map(|path| utils::path::size(path).unwrap_or(0)).sum::<u64>();
+        if needed > max {
+            bail!(
+                "Trashing {} is more than the trash quota of {}",
+                utils::human_size(needed),
+                utils::human_size(max)
+            );
+        }
+        let items: Vec<_> = list::list_only(list::Scope::Home)?
+            .into_iter()
+            .map(|item| {
+                let size = list::item_size(&item).unwrap_or(0);
+                (item, size)
+            })
+            .collect();
+        let mut used = items.iter().map(|(_, size)| size).sum::<u64>();
+        if used + needed <= max {
+            return Ok(Vec::new());
+        }
+        if self.policy.unwrap_or(QuotaPolicy::Reject) == QuotaPolicy::Reject {
+            bail!(
+                "Trashing {} would grow the trash to {}, which is over the quota of {}",
+                utils::human_size(needed),
+                utils::human_size(used + needed),
+                utils::human_size(max)
+            );
+        }
+        let mut purged = Vec::new();
+        // the items are sorted newest first
+        for (item, size) in items.into_iter().rev() {
+            if used + needed <= max {
+                break;
+            }
+            used -= size;
+            purged.push(item);
+        }
+        trash::os_limited::purge_all(purged.iter().map(utils::clone_trash_item))?;
+        purged.reverse();
+        Ok(purged)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn put_contents() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = super::super::utils::use_home_trash_in(dir.path());
+        let downloads = dir.path().join("Downloads");
+        fs::create_dir_all(downloads.join("sub")).unwrap();
+        fs::write(downloads.join("sub/b"), "").unwrap();
+        fs::write(downloads.join("a"), "").unwrap();
+
+        let config_args = app::ConfigArgs::parse_from(["trashy"]);
+        let args = PutArgs::parse_from([Path::new("put"), Path::new("--contents"), &downloads]);
+        let mut summary = None;
+        let report =
+            args.put_contents(&downloads, &config_args, Some(|s| summary = Some(s))).unwrap();
+        assert_eq!(report.trashed, [downloads.join("a"), downloads.join("sub")]);
+        assert!(report.failed.is_empty());
+        assert_eq!(fs::read_dir(&downloads).unwrap().count(), 0);
+        assert_eq!(summary.map(|s| s.items), Some(2));
+        let original_paths: HashSet<_> = list::list_scoped(list::Scope::Home)
+            .unwrap()
+            .iter()
+            .map(TrashItem::original_path)
+            .collect();
+        assert_eq!(
+            original_paths,
+            [downloads.join("a"), downloads.join("sub")].into_iter().collect()
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn put_into_namespace() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = super::super::utils::use_home_trash_in(dir.path());
+        let path = dir.path().join("a.txt");
+        let config_args = app::ConfigArgs::parse_from(["trashy"]);
+        for namespace in ["one", "two"] {
+            fs::write(&path, namespace).unwrap();
+            let args = PutArgs::parse_from([
+                Path::new("put"),
+                Path::new("--namespace"),
+                Path::new(namespace),
+                &path,
+            ]);
+            args.put_all(&config_args).unwrap();
+        }
+
+        let home = TrashDir::home().unwrap();
+        let mut entries: Vec<_> = home
+            .info_paths()
+            .iter()
+            .map(|info_path| {
+                let name = list::files_path_from_info_path(info_path);
+                let info = trash_dir::read_info(info_path).unwrap();
+                let contents = fs::read_to_string(&name).unwrap();
+                (
+                    name.file_name().unwrap().to_owned(),
+                    info.namespace().unwrap().to_owned(),
+                    contents,
+                )
+            })
+            .collect();
+        entries.sort();
+        // the names are unique across the whole trash, not only within a namespace
+        assert_eq!(
+            entries,
+            [
+                ("a.txt".into(), "one".into(), "one".into()),
+                ("a.txt.2".into(), "two".into(), "two".into())
+            ]
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn make_room_without_paths_over_quota() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = super::super::utils::use_home_trash_in(dir.path());
+        let trashed = dir.path().join("trashed");
+        fs::write(&trashed, vec![0; 2048]).unwrap();
+        trash::delete(&trashed).unwrap();
+
+        // a path on another drive goes into the trash of that drive, not into the home trash
+        for policy in ["reject", "purge-oldest"] {
+            let quota = TrashQuota::parse_from([
+                "quota",
+                "--max-trash-size",
+                "1K",
+                "--quota-policy",
+                policy,
+            ]);
+            assert!(quota.make_room(&[]).unwrap().is_empty());
+        }
+        assert_eq!(list::list_only(list::Scope::Home).unwrap().len(), 1);
+
+        let other = dir.path().join("other");
+        fs::write(&other, "").unwrap();
+        let quota = TrashQuota::parse_from(["quota", "--max-trash-size", "1K"]);
+        assert!(quota.make_room(&[&other]).is_err());
+    }
+
+    #[test]
+    fn check_hardlinkable() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("file");
+        fs::write(&file, "").unwrap();
+        assert_eq!(super::check_hardlinkable(std::slice::from_ref(&file), dir.path()), Ok(()));
+        let error = super::check_hardlinkable(&[file, dir.path().to_path_buf()], dir.path());
+        assert_eq!(
+            error,
+            Err(CannotHardlink { path: dir.path().to_path_buf(), reason: "it is a directory" })
+        );
+        let missing = dir.path().join("missing");
+        let error =
+            super::check_hardlinkable(std::slice::from_ref(&missing), dir.path()).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Cannot hardlink '{}' into the trash because it does not exist",
+                missing.display()
+            )
+        );
+    }
+
+    #[test]
+    fn check_not_recursive() {
+        let dir = tempfile::tempdir().unwrap();
+        let empty = dir.path().join("empty");
+        let full = dir.path().join("full");
+        fs::create_dir(&empty).unwrap();
+        fs::create_dir(&full).unwrap();
+        fs::write(full.join("file"), "").unwrap();
+
+        assert_eq!(super::check_not_recursive(&[empty.clone(), full.join("file")]), Ok(()));
+        assert_eq!(
+            super::check_not_recursive(&[empty, full.clone()]),
+            Err(DirectoryNotEmpty { path: full })
+        );
+    }
+
+    #[test]
+    fn check_path_len() {
+        let paths = [PathBuf::from("/tmp/a")];
+        assert_eq!(super::check_path_len(&paths, 6), Ok(()));
+        assert_eq!(
+            super::check_path_len(&paths, 5),
+            Err(PathTooLong { path: paths[0].clone(), len: 6, max: 5 })
+        );
     }
 }
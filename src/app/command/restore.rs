@@ -1,32 +1,166 @@
-use anyhow::Result;
+use std::{
+    collections::HashSet,
+    error, fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{bail, Context, Result};
 
-use crate::{range_syntax, trash_item::MaybeIndexedTrashItems};
-use clap::Parser;
+use crate::{
+    print, range_syntax,
+    summary::{Operation, OperationSummary},
+    trash_dir::{self, TrashDir},
+    trash_item::{self, MaybeIndexedTrashItems},
+    undo::UndoToken,
+    utils,
+};
+use clap::{Parser, ValueEnum};
 use either::Either::*;
+use trash::TrashItem;
 
 use crate::app;
 
 use super::{
     list,
-    utils::{Force, Ranges},
+    utils::{Force, Interactive, Ranges},
 };
 
 #[derive(Debug, Parser)]
 // wo wthis
 pub struct Args {
     #[clap(flatten)]
-    query_args: list::QueryArgs,
+    pub(super) query_args: list::QueryArgs,
 
     #[clap(flatten)]
     ranges: Ranges,
 
     #[clap(flatten)]
     force: Force,
+
+    #[clap(flatten)]
+    interactive: Interactive,
+
+    /// What to do when the original path of an item is taken
+    ///
+    /// Declare how to handle a file that now exists at the original path:
+    ///    'rename':     restore the item next to it with a number added to its name, like 'file (1).txt'
+    ///    'fail':       do not restore anything
+    #[arg(long, value_enum, default_value_t = ConflictPolicy::Rename, verbatim_doc_comment)]
+    on_conflict: ConflictPolicy,
+
+    /// Give restored items back the owner recorded in their info file
+    ///
+    /// Some tools record the user and group that owned a file with 'Uid=' and 'Gid=' keys.
+    /// Changing the owner usually needs root, so without the privileges to do it the item is
+    /// restored as it is and a warning is printed. Items without these keys are not changed.
+    #[arg(long, verbatim_doc_comment)]
+    restore_ownership: bool,
+
+    /// Restore the entries of tokens printed by 'trashy put --undo-token'
+    ///
+    /// Exactly the entries that were trashed are restored, without asking for confirmation.
+    #[arg(long, value_name = "TOKEN", conflicts_with = "ranges", verbatim_doc_comment)]
+    undo: Vec<UndoToken>,
+
+    /// Move the trashed files that have no info file into a directory
+    ///
+    /// Their original path is unknown, so they need a destination.
+    /// Files whose name is taken in the destination get a number added to their name.
+    /// To find these files, use 'trashy list --orphans'.
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = ["ranges", "undo"],
+        verbatim_doc_comment
+    )]
+    orphans_to: Option<PathBuf>,
+
+    /// Restore the item whose original file name is NAME, wherever it was trashed from
+    ///
+    /// When several items have this name, like files with the same name from different
+    /// directories, they are listed with their original directories and deletion dates,
+    /// and nothing is restored. Pick the ones to restore by their index with '--ranges', like
+    /// 'trashy restore --name notes.txt --ranges 1'.
+    #[arg(
+        long,
+        value_name = "NAME",
+        conflicts_with_all = list::QueryArgs::CONFLICTS,
+        conflicts_with_all = ["undo", "orphans_to"],
+        verbatim_doc_comment
+    )]
+    name: Option<String>,
+
+    /// Restore every item below this directory, keeping its whole original path
+    ///
+    /// An item trashed from '/home/a/x' is restored to 'DIR/home/a/x', so nothing is put back
+    /// into the live filesystem. Items whose path below DIR is taken are not restored.
+    #[arg(
+        long,
+        value_name = "DIR",
+        conflicts_with_all = list::QueryArgs::CONFLICTS,
+        conflicts_with_all = ["ranges", "undo", "orphans_to", "name"],
+        verbatim_doc_comment
+    )]
+    under: Option<PathBuf>,
+
+    /// Show what is in the way of every item instead of restoring anything
+    ///
+    /// For each item this tells whether its original path is free, taken by a file of the same type,
+    /// taken by a file of another type, or taken by another item that is restored before it.
+    /// Use this to pick '--on-conflict' before restoring.
+    #[arg(long, conflicts_with_all = ["undo", "orphans_to", "under"], verbatim_doc_comment)]
+    dry_run: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictPolicy {
+    Rename,
+    Fail,
+}
+
+/// How `restore` puts items back.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RestoreOptions {
+    pub on_conflict: ConflictPolicy,
+    /// Give items back the owner of their `Uid=` and `Gid=` keys
+    pub restore_ownership: bool,
+}
+
+#[cfg(test)]
+impl RestoreOptions {
+    fn new(on_conflict: ConflictPolicy) -> RestoreOptions {
+        RestoreOptions { on_conflict, restore_ownership: false }
+    }
 }
 
 impl Args {
     pub fn run(&self, config_args: &app::ConfigArgs) -> Result<()> {
-        let restore: Box<dyn Fn(_) -> _> = if self.force.force {
+        let options = RestoreOptions {
+            on_conflict: self.on_conflict,
+            restore_ownership: self.restore_ownership,
+        };
+        let restore = |items| restore(items, options, config_args.on_complete());
+        if !self.undo.is_empty() {
+            for token in &self.undo {
+                token.clone().undo()?;
+            }
+            return Ok(());
+        }
+        if let Some(dir) = &self.orphans_to {
+            let orphans = list::list_orphans(self.query_args.scope())?;
+            return restore_orphans(&orphans, dir, self.force.force);
+        }
+        if let Some(new_root) = &self.under {
+            return restore_under(self.query_args.scope(), new_root);
+        }
+
+        let restore: Box<dyn Fn(_) -> _> = if self.dry_run {
+            Box::new(|items| dry_run(items, self.on_conflict))
+        } else if self.interactive.interactive {
+            Box::new(|items| {
+                super::utils::on_each_item_with_prompt(items, config_args, "restored", restore)
+            })
+        } else if self.force.force {
             Box::new(restore)
         } else {
             Box::new(|items| {
@@ -34,9 +168,37 @@ impl Args {
             })
         };
 
+        if let Some(name) = &self.name {
+            let items = find_by_name(self.query_args.scope(), name);
+            if items.is_empty() {
+                bail!("No item named {name} is in the trash");
+            }
+            if !self.ranges.ranges.is_empty() {
+                let items = list::filter_by_ranges(&items, self.ranges.parse()?)?;
+                return restore(MaybeIndexedTrashItems(Right(items)));
+            }
+            if items.len() > 1 {
+                list::display_items(&items, config_args)?;
+                bail!(AmbiguousName { name: name.clone(), candidates: items.len() });
+            }
+            return restore(MaybeIndexedTrashItems(Left(items)));
+        }
+
         let filters = self.query_args.filter_args.to_filters()?;
         if filters.is_empty() && self.ranges.ranges.is_empty() {
-            let items = list::list(self.query_args.rev, self.query_args.max, filters)?;
+            // refuse before listing anything, like the confirmation prompt
+            if super::utils::check_terminal().is_err() {
+                bail!(
+                    "There is no terminal to ask for the ranges to restore on. Pass '--ranges', \
+                     a pattern, or a filter to pick the items, like in scripts or cron jobs"
+                );
+            }
+            let items = list::list(
+                self.query_args.scope(),
+                self.query_args.rev,
+                self.query_args.max,
+                filters,
+            )?;
             list::display_items(&items, config_args)?;
             let ranges =
                 dialoguer::Input::<String>::new().with_prompt("restore ranges").interact_text()?;
@@ -46,12 +208,14 @@ impl Args {
             )?)))?
         } else if self.ranges.ranges.is_empty() {
             restore(MaybeIndexedTrashItems(Left(list::list(
+                self.query_args.scope(),
                 self.query_args.rev,
                 self.query_args.max,
                 filters,
             )?)))?
         } else {
             restore(MaybeIndexedTrashItems(Right(list::list_ranged(
+                self.query_args.scope(),
                 self.query_args.rev,
                 self.query_args.max,
                 filters,
@@ -62,7 +226,669 @@ impl Args {
     }
 }
 
-fn restore(items: MaybeIndexedTrashItems) -> Result<()> {
-    trash::os_limited::restore_all(items.items())?;
+fn restore_orphans(orphans: &[PathBuf], dir: &Path, force: bool) -> Result<()> {
+    if orphans.is_empty() {
+        println!("There are no orphaned files");
+        return Ok(());
+    }
+    if !dir.is_dir() {
+        bail!("The destination {} is not a directory", dir.display());
+    }
+    if !force {
+        let len = orphans.len();
+        let plural = if len == 1 { "" } else { "s" };
+        println!("{len} orphaned file{plural} will be moved to {}", utils::path::display(dir));
+        for path in orphans {
+            println!("{}", utils::path::display(path));
+        }
+        if !super::utils::confirm()? {
+            return Ok(());
+        }
+    }
+    for path in orphans {
+        restore_orphan(path, dir)?;
+    }
+    Ok(())
+}
+
+/// Moves the orphaned file at `path` into `dir`, adding a number to its name while it is taken.
+///
+/// Returns the path that the file was moved to.
+fn restore_orphan(path: &Path, dir: &Path) -> Result<PathBuf> {
+    let name = path.file_name().context("The orphaned file does not have a name")?;
+    let mut dest = dir.join(name);
+    let mut n = 0;
+    while dest.symlink_metadata().is_ok() {
+        n += 1;
+        dest = dir.join(utils::path::numbered_name(Path::new(name), n));
+    }
+    fs::rename(path, &dest)
+        .with_context(|| format!("Failed to move {} to {}", path.display(), dest.display()))?;
+    Ok(dest)
+}
+
+/// The directory an item would be restored into can't be written to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RestoreDestNotWritable {
+    pub dir: PathBuf,
+}
+
+impl fmt::Display for RestoreDestNotWritable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Cannot restore into '{}' because it is not writable. Check its permissions or restore \
+             as a user that can write to it",
+            self.dir.display()
+        )
+    }
+}
+
+impl error::Error for RestoreDestNotWritable {}
+
+/// An item would be restored into a trash directory, which only a corrupt or crafted info file
+/// can ask for.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UnsafeRestoreTarget {
+    pub path: PathBuf,
+}
+
+impl fmt::Display for UnsafeRestoreTarget {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Refusing to restore to '{}' because it is inside a trash directory and could \
+             overwrite what is in the trash",
+            self.path.display()
+        )
+    }
+}
+
+impl error::Error for UnsafeRestoreTarget {}
+
+/// The contents of a restored file don't match the checksum recorded by `trashy put --checksum`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChecksumMismatch {
+    pub path: PathBuf,
+    pub expected: String,
+    pub actual: String,
+}
+
+impl fmt::Display for ChecksumMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "'{}' was restored, but its contents changed while it was in the trash. Its SHA-256 \
+             is {} instead of {}",
+            self.path.display(),
+            self.actual,
+            self.expected
+        )
+    }
+}
+
+impl error::Error for ChecksumMismatch {}
+
+/// Several items have the name given to `restore --name`, so it is unclear which one to restore.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AmbiguousName {
+    pub name: String,
+    pub candidates: usize,
+}
+
+impl fmt::Display for AmbiguousName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The name {} is ambiguous, since {} items in the trash have it. Pick the ones to \
+             restore by their index, like 'trashy restore --name {} --ranges 0'",
+            self.name, self.candidates, self.name
+        )
+    }
+}
+
+impl error::Error for AmbiguousName {}
+
+/// Restores every item in `scope` below `new_root`, keeping their whole original paths.
+fn restore_under(scope: list::Scope, new_root: &Path) -> Result<()> {
+    if !cfg!(target_os = "linux") {
+        bail!("--under is only supported on linux");
+    }
+    let mut restored = 0;
+    let mut failed = 0;
+    for trash_dir in scope.trash_dirs() {
+        let report = trash_dir.restore_all_under(new_root)?;
+        for entry in &report.failed {
+            print::err_display(format!("{}: {:#}", entry.info_path.display(), entry.error));
+        }
+        restored += report.restored.len();
+        failed += report.failed.len();
+    }
+    let plural = if restored == 1 { "" } else { "s" };
+    println!("{restored} item{plural} restored under {}", utils::path::display(new_root));
+    if failed > 0 {
+        let plural = if failed == 1 { "" } else { "s" };
+        bail!("{failed} item{plural} could not be restored");
+    }
+    Ok(())
+}
+
+/// Returns the items in `scope` whose original file name is `name`, newest first.
+fn find_by_name(scope: list::Scope, name: &str) -> Vec<TrashItem> {
+    let items: Vec<_> =
+        scope.trash_dirs().iter().flat_map(|trash_dir| trash_dir.find_by_basename(name)).collect();
+    trash_item::newest_first(items).collect()
+}
+
+/// Fails before anything is restored if any of `items` can't be restored into its original parent,
+/// or would be restored into a trash directory.
+fn check_destinations(items: &[TrashItem]) -> Result<()> {
+    let trash_dirs = trash_dir::all_trash_dirs();
+    for item in items {
+        let path = item.original_path();
+        if trash_dir::is_inside_trash(&path, &trash_dirs) {
+            return Err(UnsafeRestoreTarget { path }.into());
+        }
+        if let Some(dir) = trash_dir::unwritable_ancestor(&item.original_parent) {
+            return Err(RestoreDestNotWritable { dir: dir.to_path_buf() }.into());
+        }
+    }
+    Ok(())
+}
+
+/// What is at the original path of an item that is about to be restored.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Destination {
+    Free,
+    /// A file of the same type as the trashed file is in the way
+    SameType(&'static str),
+    /// A file of another type than the trashed file is in the way
+    OtherType {
+        existing: &'static str,
+        trashed: &'static str,
+    },
+    /// An item that is restored before this one has the same original path
+    Selected,
+}
+
+/// Finds out what is in the way of each of `items`, in the order they are restored in.
+fn destinations(items: &[TrashItem]) -> Vec<Destination> {
+    let mut claimed = HashSet::new();
+    items
+        .iter()
+        .map(|item| {
+            let original_path = item.original_path();
+            let existing = match fs::symlink_metadata(&original_path) {
+                Ok(meta) => list::file_type_name(meta.file_type()),
+                Err(_) if claimed.insert(original_path) => return Destination::Free,
+                Err(_) => return Destination::Selected,
+            };
+            let trashed =
+                fs::symlink_metadata(list::files_path_from_info_path(Path::new(&item.id)))
+                    .map_or("missing", |meta| list::file_type_name(meta.file_type()));
+            if existing == trashed {
+                Destination::SameType(existing)
+            } else {
+                Destination::OtherType { existing, trashed }
+            }
+        })
+        .collect()
+}
+
+fn dry_run(items: MaybeIndexedTrashItems, on_conflict: ConflictPolicy) -> Result<()> {
+    if !cfg!(target_os = "linux") {
+        bail!("'--dry-run' is only supported on linux");
+    }
+    let items: Vec<_> = items.items().collect();
+    let destinations = destinations(&items);
+    for (item, destination) in items.iter().zip(&destinations) {
+        let path = utils::path::display(&item.original_path());
+        match destination {
+            Destination::Free => println!("{path}: free"),
+            Destination::SameType("directory") => {
+                println!("{path}: taken by a directory");
+                // the directories would not be merged, but this shows what is in both of them
+                let info_path = Path::new(&item.id);
+                let trash_dir = TrashDir::containing(info_path)
+                    .context("The info file is not in a trash directory")?;
+                for conflict in trash_dir.restore_conflicts(info_path)? {
+                    println!("    {} exists", utils::path::display(&conflict));
+                }
+            }
+            Destination::SameType(file_type) => println!("{path}: taken by a {file_type}"),
+            Destination::OtherType { existing, trashed } => {
+                println!("{path}: taken by a {existing}, but the item is a {trashed}")
+            }
+            Destination::Selected => println!("{path}: taken by an item restored before it"),
+        }
+    }
+    let conflicts = destinations.iter().filter(|d| **d != Destination::Free).count();
+    let plural = if items.len() == 1 { "" } else { "s" };
+    println!("{conflicts} of {} item{plural} would conflict", items.len());
+    if conflicts > 0 {
+        match on_conflict {
+            ConflictPolicy::Rename => {
+                println!("With '--on-conflict rename' they are restored with a number added to their name")
+            }
+            ConflictPolicy::Fail => {
+                println!("With '--on-conflict fail' nothing would be restored")
+            }
+        }
+    }
+    Ok(())
+}
+
+fn restore(
+    items: MaybeIndexedTrashItems,
+    options: RestoreOptions,
+    on_complete: Option<impl FnOnce(OperationSummary)>,
+) -> Result<()> {
+    let items: Vec<_> = items.items().collect();
+    check_destinations(&items)?;
+    // the sizes can't be read anymore once the items left the trash
+    let sizes: Vec<_> = match on_complete {
+        Some(_) => items.iter().map(list::item_size).collect(),
+        None => Vec::new(),
+    };
+    // the info files are gone after restoring, so the owners are read first
+    let owners: Vec<_> = items
+        .iter()
+        .map(|item| options.restore_ownership.then(|| recorded_owner(item)).flatten())
+        .collect();
+    let checksums: Vec<_> = items.iter().map(recorded_checksum).collect();
+    if options.on_conflict == ConflictPolicy::Fail {
+        let paths: Vec<_> = items.iter().map(TrashItem::original_path).collect();
+        trash::os_limited::restore_all(items)?;
+        for (path, owner) in paths.iter().zip(owners) {
+            if let Some(owner) = owner {
+                restore_owner(path, owner);
+            }
+        }
+        for (path, checksum) in paths.iter().zip(&checksums) {
+            if let Some(checksum) = checksum {
+                verify_checksum(path, checksum)?;
+            }
+        }
+        if let Some(on_complete) = on_complete {
+            on_complete(OperationSummary::new(Operation::Restore, sizes));
+        }
+        return Ok(());
+    }
+    // one item that can't be restored does not stop the others
+    let mut failed = 0;
+    let mut mismatched = 0;
+    let mut restored_sizes = Vec::new();
+    for (i, (item, owner)) in items.into_iter().zip(owners).enumerate() {
+        let original_path = item.original_path();
+        let restored = restore_renaming(item);
+        if let (Ok(restored_path), Some(owner)) = (&restored, owner) {
+            restore_owner(restored_path, owner);
+        }
+        if let (Ok(restored_path), Some(checksum)) = (&restored, &checksums[i]) {
+            if let Err(e) = verify_checksum(restored_path, checksum) {
+                print::err_display(format!("{e:#}"));
+                mismatched += 1;
+            }
+        }
+        if restored.is_ok() {
+            restored_sizes.extend(sizes.get(i).copied());
+        }
+        match restored {
+            Ok(restored_path) if restored_path != original_path => println!(
+                "{} already exists, so it was restored to {}",
+                utils::path::display(&original_path),
+                utils::path::display(&restored_path)
+            ),
+            Ok(_) => (),
+            Err(e) => {
+                print::err_display(format!(
+                    "Failed to restore {}: {e:#}",
+                    utils::path::display(&original_path)
+                ));
+                failed += 1;
+            }
+        }
+    }
+    if let Some(on_complete) = on_complete {
+        on_complete(OperationSummary::new(Operation::Restore, restored_sizes));
+    }
+    if failed > 0 {
+        let plural = if failed == 1 { "" } else { "s" };
+        bail!("{failed} item{plural} could not be restored");
+    }
+    if mismatched > 0 {
+        let plural = if mismatched == 1 { "" } else { "s" };
+        bail!("{mismatched} restored item{plural} did not match the recorded checksum");
+    }
+    Ok(())
+}
+
+/// Returns the user and group ids that the info file of `item` records, if it records any.
+fn recorded_owner(item: &TrashItem) -> Option<(Option<u32>, Option<u32>)> {
+    let info = trash_dir::read_info(Path::new(&item.id)).ok()?;
+    (info.uid().is_some() || info.gid().is_some()).then(|| (info.uid(), info.gid()))
+}
+
+/// Returns the checksum that `trashy put --checksum` recorded for `item`, if it recorded one.
+fn recorded_checksum(item: &TrashItem) -> Option<String> {
+    let info = trash_dir::read_info(Path::new(&item.id)).ok()?;
+    Some(info.sha256()?.to_owned())
+}
+
+/// Fails when the contents of the restored `path` don't have the `expected` checksum.
+fn verify_checksum(path: &Path, expected: &str) -> Result<()> {
+    let actual = utils::path::sha256(path)
+        .with_context(|| format!("Failed to read {} to verify it", path.display()))?;
+    if actual != expected {
+        let (path, expected) = (path.to_path_buf(), expected.to_owned());
+        return Err(ChecksumMismatch { path, expected, actual }.into());
+    }
     Ok(())
 }
+
+/// Gives the restored `path` back to the user and group it was trashed with.
+///
+/// Without the privileges to do that, the restored item keeps its current owner.
+fn restore_owner(path: &Path, (uid, gid): (Option<u32>, Option<u32>)) {
+    if let Err(e) = utils::path::set_owner(path, uid, gid) {
+        print::warn_display(format!(
+            "Could not give {} back to its original owner: {e}",
+            utils::path::display(path)
+        ));
+    }
+}
+
+/// Restores `item`, adding a number to its name while its original path is taken.
+///
+/// Returns the path that the item was restored to.
+fn restore_renaming(mut item: TrashItem) -> Result<PathBuf> {
+    let original_path = item.original_path();
+    let mut n = 0;
+    loop {
+        match trash::os_limited::restore_all([utils::clone_trash_item(&item)]) {
+            Ok(()) => return Ok(item.original_path()),
+            Err(trash::Error::RestoreCollision { .. }) => {
+                n += 1;
+                item.name = utils::path::numbered_name(&original_path, n).to_string_lossy().into();
+            }
+            Err(e) => return Err(e.into()),
+        }
+    }
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+    use std::os::unix::fs::symlink;
+
+    use crate::trash_info::TrashInfo;
+    use chrono::Local;
+
+    use super::super::utils::use_home_trash_in;
+    use super::*;
+
+    const NO_SUMMARY: Option<fn(OperationSummary)> = None;
+
+    fn trashed_from(path: &Path) -> Vec<TrashItem> {
+        list::list_scoped(list::Scope::Home)
+            .unwrap()
+            .into_iter()
+            .filter(|item| item.original_path() == path)
+            .collect()
+    }
+
+    #[test]
+    fn restore_dangling_symlink() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        let link = dir.path().join("link");
+        symlink(dir.path().join("missing"), &link).unwrap();
+        trash::delete(&link).unwrap();
+        assert!(link.symlink_metadata().is_err());
+
+        let items = trashed_from(&link);
+        restore(
+            MaybeIndexedTrashItems(Left(items)),
+            RestoreOptions::new(ConflictPolicy::Fail),
+            NO_SUMMARY,
+        )
+        .unwrap();
+        assert!(link.symlink_metadata().unwrap().file_type().is_symlink());
+        assert!(!link.exists());
+    }
+
+    #[test]
+    fn restore_renames_when_path_is_taken() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        let path = dir.path().join("file.txt");
+        fs::write(&path, "trashed").unwrap();
+        trash::delete(&path).unwrap();
+        fs::write(&path, "unrelated").unwrap();
+        fs::write(dir.path().join("file (1).txt"), "also unrelated").unwrap();
+
+        let item = trashed_from(&path).pop().unwrap();
+        let restored = restore_renaming(item).unwrap();
+        assert_eq!(restored, dir.path().join("file (2).txt"));
+        assert_eq!(fs::read_to_string(&restored).unwrap(), "trashed");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "unrelated");
+        assert!(trashed_from(&path).is_empty());
+    }
+
+    #[test]
+    fn restore_summary() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        let paths = [dir.path().join("a"), dir.path().join("b")];
+        for path in &paths {
+            fs::write(path, "trashed").unwrap();
+            trash::delete(path).unwrap();
+        }
+        fs::write(&paths[1], "unrelated").unwrap();
+
+        let items = paths.iter().flat_map(|path| trashed_from(path)).collect();
+        let mut summary = None;
+        let on_complete = |s| summary = Some(s);
+        restore(
+            MaybeIndexedTrashItems(Left(items)),
+            RestoreOptions::new(ConflictPolicy::Rename),
+            Some(on_complete),
+        )
+        .unwrap();
+        assert_eq!(
+            summary,
+            Some(OperationSummary { operation: Operation::Restore, items: 2, bytes: Some(14) })
+        );
+    }
+
+    #[test]
+    fn destinations() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        let paths: Vec<_> =
+            ["free", "same", "other", "twice"].iter().map(|name| dir.path().join(name)).collect();
+        for path in &paths {
+            fs::write(path, "trashed").unwrap();
+            trash::delete(path).unwrap();
+        }
+        fs::write(&paths[3], "trashed again").unwrap();
+        trash::delete(&paths[3]).unwrap();
+        fs::write(&paths[1], "unrelated").unwrap();
+        fs::create_dir(&paths[2]).unwrap();
+
+        let items: Vec<_> = paths.iter().flat_map(|path| trashed_from(path)).collect();
+        assert_eq!(
+            super::destinations(&items),
+            [
+                Destination::Free,
+                Destination::SameType("file"),
+                Destination::OtherType { existing: "directory", trashed: "file" },
+                Destination::Free,
+                Destination::Selected,
+            ]
+        );
+    }
+
+    #[test]
+    fn restore_orphan_renames_when_name_is_taken() {
+        let dir = tempfile::tempdir().unwrap();
+        let orphan = dir.path().join("files/file");
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::write(&orphan, "orphan").unwrap();
+        fs::write(dir.path().join("file"), "unrelated").unwrap();
+
+        let restored = restore_orphan(&orphan, dir.path()).unwrap();
+        assert_eq!(restored, dir.path().join("file (1)"));
+        assert_eq!(fs::read_to_string(restored).unwrap(), "orphan");
+        assert!(!orphan.exists());
+    }
+
+    #[test]
+    #[ignore = "root can write to any directory, run with --ignored as another user"]
+    fn restore_into_unwritable_dir() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        let parent = dir.path().join("read-only");
+        let path = parent.join("file.txt");
+        fs::create_dir(&parent).unwrap();
+        fs::write(&path, "trashed").unwrap();
+        trash::delete(&path).unwrap();
+        fs::set_permissions(&parent, fs::Permissions::from_mode(0o500)).unwrap();
+
+        let items = trashed_from(&path);
+        let error = restore(
+            MaybeIndexedTrashItems(Left(items)),
+            RestoreOptions::new(ConflictPolicy::Rename),
+            NO_SUMMARY,
+        )
+        .unwrap_err()
+        .downcast::<RestoreDestNotWritable>()
+        .unwrap();
+        assert_eq!(error, RestoreDestNotWritable { dir: parent.clone() });
+        assert_eq!(trashed_from(&path).len(), 1);
+        fs::set_permissions(&parent, fs::Permissions::from_mode(0o700)).unwrap();
+    }
+
+    #[test]
+    fn restore_into_trash() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        let victim = dir.path().join("victim");
+        fs::write(&victim, "trashed").unwrap();
+        trash::delete(&victim).unwrap();
+        let trash = TrashDir::home().unwrap();
+        let target = trash.path().join("files/victim");
+        fs::write(trash.path().join("files/crafted"), "crafted").unwrap();
+        let info = TrashInfo::new(&target, Local::now().naive_local());
+        trash_dir::write_info(&trash.path().join("info/crafted.trashinfo"), &info).unwrap();
+
+        let items = trashed_from(&target);
+        let error = restore(
+            MaybeIndexedTrashItems(Left(items)),
+            RestoreOptions::new(ConflictPolicy::Rename),
+            NO_SUMMARY,
+        )
+        .unwrap_err()
+        .downcast::<UnsafeRestoreTarget>()
+        .unwrap();
+        assert_eq!(error, UnsafeRestoreTarget { path: target.clone() });
+        assert_eq!(fs::read_to_string(&target).unwrap(), "trashed");
+        assert_eq!(trashed_from(&target).len(), 1);
+    }
+
+    #[test]
+    fn restore_verifies_checksums() {
+        use std::io::Write;
+
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        let paths = [dir.path().join("intact"), dir.path().join("corrupt")];
+        for path in &paths {
+            fs::write(path, "trashed").unwrap();
+            let checksum = utils::path::sha256(path).unwrap();
+            trash::delete(path).unwrap();
+            let item = trashed_from(path).pop().unwrap();
+            let info = trash_dir::read_info(Path::new(&item.id)).unwrap().with_sha256(&checksum);
+            trash_dir::write_info(Path::new(&item.id), &info).unwrap();
+        }
+        let item = trashed_from(&paths[1]).pop().unwrap();
+        let mut file = fs::OpenOptions::new()
+            .append(true)
+            .open(list::files_path_from_info_path(Path::new(&item.id)))
+            .unwrap();
+        write!(file, " and changed").unwrap();
+
+        restore(
+            MaybeIndexedTrashItems(Left(trashed_from(&paths[0]))),
+            RestoreOptions::new(ConflictPolicy::Fail),
+            NO_SUMMARY,
+        )
+        .unwrap();
+        let error = restore(
+            MaybeIndexedTrashItems(Left(trashed_from(&paths[1]))),
+            RestoreOptions::new(ConflictPolicy::Fail),
+            NO_SUMMARY,
+        )
+        .unwrap_err()
+        .downcast::<ChecksumMismatch>()
+        .unwrap();
+        assert_eq!(error.path, paths[1]);
+        assert_eq!(error.expected, utils::path::sha256(&paths[0]).unwrap());
+        assert_eq!(fs::read_to_string(&paths[1]).unwrap(), "trashed and changed");
+    }
+
+    #[test]
+    fn restore_ownership() {
+        use std::{io::Write, os::unix::fs::MetadataExt};
+
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        let paths = [dir.path().join("recorded"), dir.path().join("unrecorded")];
+        for path in &paths {
+            fs::write(path, "trashed").unwrap();
+            trash::delete(path).unwrap();
+        }
+        let item = trashed_from(&paths[0]).pop().unwrap();
+        let mut info = fs::OpenOptions::new().append(true).open(&item.id).unwrap();
+        write!(info, "Uid=1234\nGid=5678\n").unwrap();
+
+        let items = paths.iter().flat_map(|path| trashed_from(path)).collect();
+        let options =
+            RestoreOptions { restore_ownership: true, ..RestoreOptions::new(ConflictPolicy::Fail) };
+        restore(MaybeIndexedTrashItems(Left(items)), options, NO_SUMMARY).unwrap();
+        let owner = |path: &Path| {
+            let meta = fs::symlink_metadata(path).unwrap();
+            (meta.uid(), meta.gid())
+        };
+        let current = owner(dir.path());
+        // only root may give files away, everyone else restores them with a warning
+        if trash_dir::current_uid() == 0 {
+            assert_eq!(owner(&paths[0]), (1234, 5678));
+        } else {
+            assert_eq!(owner(&paths[0]), current);
+        }
+        assert_eq!(owner(&paths[1]), current);
+        assert!(paths.iter().all(|path| trashed_from(path).is_empty()));
+    }
+
+    #[test]
+    fn restore_fails_when_path_is_taken() {
+        let dir = tempfile::tempdir().unwrap();
+        let _guard = use_home_trash_in(dir.path());
+        let path = dir.path().join("file.txt");
+        fs::write(&path, "trashed").unwrap();
+        trash::delete(&path).unwrap();
+        fs::write(&path, "unrelated").unwrap();
+
+        let items = trashed_from(&path);
+        assert!(restore(
+            MaybeIndexedTrashItems(Left(items)),
+            RestoreOptions::new(ConflictPolicy::Fail),
+            NO_SUMMARY,
+        )
+        .is_err());
+        assert_eq!(fs::read_to_string(&path).unwrap(), "unrelated");
+        assert_eq!(trashed_from(&path).len(), 1);
+    }
+}
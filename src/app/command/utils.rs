@@ -1,7 +1,12 @@
+use std::io::{self, Write};
+
 use super::list;
-use crate::{app, range_set::RangeSet, range_syntax, trash_item::MaybeIndexedTrashItems};
-use anyhow::Result;
+use crate::{
+    app, print, range_set::RangeSet, range_syntax, trash_item::MaybeIndexedTrashItems, utils,
+};
+use anyhow::{bail, Result};
 use clap::{ArgAction, Parser};
+use either::Either::Left;
 
 pub fn on_items_with_prompt(
     items: MaybeIndexedTrashItems,
@@ -9,29 +14,105 @@ pub fn on_items_with_prompt(
     action_name: &str,
     f: impl FnOnce(MaybeIndexedTrashItems) -> Result<()>,
 ) -> Result<()> {
-    use dialoguer::Confirm;
-
+    // refuse before listing anything
+    check_terminal()?;
     let len = items.len();
     let plural = if len == 1 { "" } else { "s" };
     println!("{len} item{plural} will be {action_name}");
     list::display_indexed_items(items.indexed_items(), config_args)?;
-    if Confirm::new().with_prompt("Are you sure?").interact()? {
+    if confirm()? {
         f(items)?;
     }
     Ok(())
 }
 
+/// Asks whether to go on.
+///
+/// Without a terminal to ask on this fails instead of answering for the user, so a script
+/// has to pass '--yes' to make its intent explicit.
+pub fn confirm() -> Result<bool> {
+    check_terminal()?;
+    Ok(dialoguer::Confirm::new().with_prompt("Are you sure?").interact()?)
+}
+
+/// Fails when there is no terminal to prompt on, since stdin or stderr is not one.
+pub fn check_terminal() -> Result<()> {
+    if !atty::is(atty::Stream::Stdin) || !atty::is(atty::Stream::Stderr) {
+        bail!(
+            "There is no terminal to ask for confirmation on. Pass '--yes' to go on without \
+             confirmation, like in scripts or cron jobs"
+        );
+    }
+    Ok(())
+}
+
+/// Asks about every item on its own and only passes on the confirmed items.
+///
+/// Answers are read from stdin. When stdin is not a terminal, every item is answered with no
+/// and a warning is shown for it, so that a script can't answer the prompts by accident.
+pub fn on_each_item_with_prompt(
+    items: MaybeIndexedTrashItems,
+    config_args: &app::ConfigArgs,
+    action_name: &str,
+    f: impl FnOnce(MaybeIndexedTrashItems) -> Result<()>,
+) -> Result<()> {
+    if !atty::is(atty::Stream::Stdin) {
+        for item in items.items() {
+            print::warn_display(format!(
+                "{} was not {action_name}, since stdin is not a terminal to ask on",
+                utils::path::display(&item.original_path())
+            ));
+        }
+        return Ok(());
+    }
+    let stdin = io::stdin();
+    let mut confirmed = Vec::new();
+    for item in items.items() {
+        let size = list::item_size(&item).map_or_else(|| "unknown size".into(), utils::human_size);
+        eprint!(
+            "{} (trashed {}, {size}) will be {action_name}. Continue? [y/N] ",
+            utils::path::display(&item.original_path()),
+            list::display_item_date(&item, config_args.time_display_mode),
+        );
+        io::stderr().flush()?;
+        let mut answer = String::new();
+        if stdin.read_line(&mut answer)? == 0 {
+            // treat EOF as answering no to this and every following item
+            eprintln!();
+            break;
+        }
+        if matches!(answer.trim(), "y" | "Y" | "yes") {
+            confirmed.push(item);
+        }
+    }
+    if !confirmed.is_empty() {
+        f(MaybeIndexedTrashItems(Left(confirmed)))?;
+    }
+    Ok(())
+}
+
 #[derive(Debug, Parser)]
 pub struct Force {
     /// Skip confirmation
     ///
     /// By default, 'trashy' will ask for confirmation before restoring or permanently removing files.
-    /// You can opt out of this by adding '--force'.
-    /// This can be useful in scripts.
-    #[arg(short, long)]
+    /// You can opt out of this by adding '--force' or its alias '--yes'.
+    /// Without a terminal to ask on, like in scripts or cron jobs, 'trashy' refuses to go on
+    /// unless one of them is given.
+    #[arg(short, long, visible_alias = "yes", short_alias = 'y')]
     pub force: bool,
 }
 
+#[derive(Debug, Parser)]
+pub struct Interactive {
+    /// Prompt before every item
+    ///
+    /// Instead of asking once for all items, 'trashy' will ask about each item on its own.
+    /// When stdin is not a terminal, every item is answered with no and a warning is shown.
+    #[arg(short, long, conflicts_with = "force")]
+    pub interactive: bool,
+}
+
 #[derive(Debug, Parser)]
 pub struct Ranges {
     /// Filter by ranges
@@ -58,3 +139,16 @@ impl Ranges {
         self.ranges.iter().flat_map(|s| range_syntax::parse_ranges(s)).collect()
     }
 }
+
+/// Serializes the tests that point the home trash somewhere else.
+#[cfg(test)]
+static HOME_TRASH: once_cell::sync::Lazy<std::sync::Mutex<()>> =
+    once_cell::sync::Lazy::new(std::sync::Mutex::default);
+
+/// Points the home trash into `dir` until the returned guard is dropped.
+#[cfg(test)]
+pub fn use_home_trash_in(dir: &std::path::Path) -> std::sync::MutexGuard<'static, ()> {
+    let guard = HOME_TRASH.lock().unwrap_or_else(|e| e.into_inner());
+    std::env::set_var("XDG_DATA_HOME", dir.join("data"));
+    guard
+}
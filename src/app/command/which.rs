@@ -0,0 +1,40 @@
+use std::path::PathBuf;
+
+use anyhow::{bail, Result};
+use clap::Parser;
+
+use crate::{trash_dir, utils};
+
+#[derive(Debug, Parser)]
+pub struct Args {
+    /// The paths to look up, which don't have to exist
+    #[arg(required = true)]
+    paths: Vec<PathBuf>,
+}
+
+impl Args {
+    pub fn run(&self) -> Result<()> {
+        if !cfg!(target_os = "linux") {
+            bail!("`trashy which` is only supported on linux");
+        }
+        for path in &self.paths {
+            match trash_dir::trash_dir_for(path) {
+                Some((rule, trash_dir)) => {
+                    let read_only = match trash_dir::read_only_mount(path) {
+                        Some(mount) => {
+                            format!(", but {} is mounted read-only", utils::path::display(&mount))
+                        }
+                        None => String::new(),
+                    };
+                    println!(
+                        "{}: {} ({rule}{read_only})",
+                        utils::path::display(path),
+                        utils::path::display(&trash_dir)
+                    )
+                }
+                None => bail!("Could not find the home trash, since $HOME is not set"),
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,194 @@
+//! The defaults read from `$XDG_CONFIG_HOME/trashy/config.toml`.
+//!
+//! The file is a toml table of `key = value` lines, like
+//!
+//! ```toml
+//! # show the oldest items at the bottom
+//! rev = true
+//! time = "precise"
+//! max-trash-size = "10G"
+//! quota-policy = "purge-oldest"
+//! ```
+//!
+//! Every key matches the flag it sets a default for. Flags given on the command line win.
+
+use std::{
+    env, fmt, fs, io,
+    marker::PhantomData,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use clap::ValueEnum;
+use serde::{de, Deserialize, Deserializer};
+
+use super::{command::put::QuotaPolicy, Status, TimeDisplayMode};
+use crate::utils;
+
+/// The defaults from the config file. Every key that is not in the file is `None`.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct Config {
+    #[serde(deserialize_with = "value_enum")]
+    pub color: Option<Status>,
+    #[serde(deserialize_with = "value_enum")]
+    pub table: Option<Status>,
+    #[serde(deserialize_with = "value_enum")]
+    pub time: Option<TimeDisplayMode>,
+    /// Put the oldest items at the bottom, like '--rev'
+    pub rev: Option<bool>,
+    #[serde(deserialize_with = "size")]
+    pub max_trash_size: Option<u64>,
+    #[serde(deserialize_with = "value_enum")]
+    pub quota_policy: Option<QuotaPolicy>,
+}
+
+impl Config {
+    /// Reads the config file, or returns the built-in defaults if there is none.
+    pub fn load() -> Result<Config> {
+        let path = match config_path() {
+            Some(path) => path,
+            None => return Ok(Config::default()),
+        };
+        let s = match fs::read_to_string(&path) {
+            Ok(s) => s,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        Config::parse(&s).with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    fn parse(s: &str) -> Result<Config> {
+        Ok(toml::from_str(s)?)
+    }
+}
+
+/// Returns `$XDG_CONFIG_HOME/trashy/config.toml` or `~/.config/trashy/config.toml`.
+fn config_path() -> Option<PathBuf> {
+    let config_home = env::var_os("XDG_CONFIG_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+            let home = env::var_os("HOME").filter(|dir| !dir.is_empty())?;
+            Some(Path::new(&home).join(".config"))
+        })?;
+    Some(config_home.join("trashy/config.toml"))
+}
+
+/// Deserializes a string with the same values as the flag of the key.
+fn value_enum<'de, D: Deserializer<'de>, T: ValueEnum>(d: D) -> Result<Option<T>, D::Error> {
+    struct Visitor<T>(PhantomData<T>);
+
+    impl<'de, T: ValueEnum> de::Visitor<'de> for Visitor<T> {
+        type Value = Option<T>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            let possible = T::value_variants()
+                .iter()
+                .filter_map(|variant| {
+                    Some(format!("\"{}\"", variant.to_possible_value()?.get_name()))
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            write!(f, "one of {possible}")
+        }
+
+        fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+            match T::from_str(s, false) {
+                Ok(t) => Ok(Some(t)),
+                Err(_) => Err(E::invalid_value(de::Unexpected::Str(s), &self)),
+            }
+        }
+    }
+
+    d.deserialize_str(Visitor(PhantomData))
+}
+
+/// Deserializes a number of bytes or a size like `"1G"`.
+fn size<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u64>, D::Error> {
+    struct Visitor;
+
+    impl<'de> de::Visitor<'de> for Visitor {
+        type Value = Option<u64>;
+
+        fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("a number of bytes or a size like \"1G\"")
+        }
+
+        fn visit_u64<E: de::Error>(self, n: u64) -> Result<Self::Value, E> {
+            Ok(Some(n))
+        }
+
+        fn visit_i64<E: de::Error>(self, n: i64) -> Result<Self::Value, E> {
+            match u64::try_from(n) {
+                Ok(n) => Ok(Some(n)),
+                Err(_) => Err(E::invalid_value(de::Unexpected::Signed(n), &self)),
+            }
+        }
+
+        fn visit_str<E: de::Error>(self, s: &str) -> Result<Self::Value, E> {
+            match utils::parse_size(s) {
+                Ok(size) => Ok(Some(size)),
+                Err(_) => Err(E::invalid_value(de::Unexpected::Str(s), &self)),
+            }
+        }
+    }
+
+    d.deserialize_any(Visitor)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse() {
+        let config = Config::parse(
+            "# defaults\n\
+             \n\
+             rev = true\n\
+             time = \"precise\" # like --time precise\n\
+             color=\"never\"\n\
+             max-trash-size = \"1G\"\n\
+             quota-policy = \"purge-oldest\"\n",
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            Config {
+                color: Some(Status::Never),
+                table: None,
+                time: Some(TimeDisplayMode::Precise),
+                rev: Some(true),
+                max_trash_size: Some(1024 * 1024 * 1024),
+                quota_policy: Some(QuotaPolicy::PurgeOldest),
+            }
+        );
+        assert_eq!(Config::parse("").unwrap(), Config::default());
+        assert_eq!(Config::parse("max-trash-size = 1_000").unwrap().max_trash_size, Some(1000));
+        assert_eq!(
+            Config::parse("table = \"never\" # \"auto\"").unwrap().table,
+            Some(Status::Never)
+        );
+    }
+
+    #[test]
+    fn parse_errors() {
+        for (s, error) in [
+            ("rev", "expected an equals"),
+            ("\nsort = true", "unknown field `sort`"),
+            ("rev = \"yes\"", "invalid type: string \"yes\", expected a boolean"),
+            (
+                "time = \"later\"",
+                "invalid value: string \"later\", expected one of \"precise\", \"imprecise\"",
+            ),
+            ("table = true", "expected one of \"auto\", \"always\", \"never\""),
+            ("max-trash-size = -1", "invalid value: integer `-1`, expected a number of bytes"),
+            ("time = \"precise", "unterminated string"),
+            ("time = precise", "invalid"),
+        ] {
+            let e = format!("{:#}", Config::parse(s).unwrap_err());
+            assert!(e.contains(error), "{e}");
+        }
+    }
+}
@@ -0,0 +1,68 @@
+use chrono::{DateTime, Local};
+
+/// A source of the current time.
+///
+/// Everything that depends on the current time takes a clock, so that it can be tested with a
+/// fixed time instead of the time of the system.
+pub trait Clock {
+    fn now_local(&self) -> DateTime<Local>;
+}
+
+/// The clock of the system.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now_local(&self) -> DateTime<Local> {
+        Local::now()
+    }
+}
+
+/// A clock that is stopped at a fixed time, for tests.
+#[cfg(test)]
+pub struct FixedClock(pub DateTime<Local>);
+
+#[cfg(test)]
+impl Clock for FixedClock {
+    fn now_local(&self) -> DateTime<Local> {
+        self.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, TimeZone};
+    use clap::Parser;
+    use trash::TrashItem;
+
+    use super::*;
+    use crate::{filter::FilterArgs, trash_item};
+
+    fn clock() -> FixedClock {
+        FixedClock(Local.timestamp_opt(1_700_000_000, 0).unwrap())
+    }
+
+    fn item_deleted_ago(ago: Duration) -> TrashItem {
+        TrashItem {
+            id: "id".into(),
+            name: "a".into(),
+            original_parent: "/".into(),
+            time_deleted: (clock().0 - ago).timestamp(),
+        }
+    }
+
+    #[test]
+    fn age() {
+        let item = item_deleted_ago(Duration::hours(3));
+        assert_eq!(trash_item::age(&item, &clock()), Duration::hours(3));
+    }
+
+    #[test]
+    fn time_filters() {
+        let filters = FilterArgs::parse_from(["trashy", "--before", "1day"])
+            .to_filters_with(&clock())
+            .unwrap();
+        assert!(filters.is_match(&item_deleted_ago(Duration::days(2))));
+        assert!(!filters.is_match(&item_deleted_ago(Duration::hours(1))));
+    }
+}
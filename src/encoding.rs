@@ -0,0 +1,146 @@
+//! Percent-encoding of the paths and file names that are stored in trash directories.
+
+use std::{
+    error, fmt,
+    path::{Path, PathBuf},
+};
+
+use percent_encoding::{AsciiSet, CONTROLS, NON_ALPHANUMERIC};
+
+/// The bytes that are left as they are when percent-encoding a path, like in the path of a URL.
+///
+/// Control characters are always encoded, since a newline would break the file format.
+const PATH_UNRESERVED: &AsciiSet =
+    &NON_ALPHANUMERIC.remove(b'/').remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+/// The bytes that have to be encoded for a path to read back as it was written.
+const PATH_MINIMAL: &AsciiSet = &CONTROLS.add(b'%');
+
+/// Which bytes of a path are percent-encoded.
+///
+/// Every policy encodes control characters, `%`, and bytes outside of ASCII, so whatever one
+/// policy writes is read back by `decode_path` no matter which policy wrote it.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum EncodePolicy {
+    /// Leaves letters, digits, `/`, `-`, `_`, `.`, and `~` as they are, like the path of a URL.
+    /// This is what trash-cli and most file managers write.
+    #[default]
+    Standard,
+    /// Only encodes what has to be encoded, keeping spaces and punctuation readable.
+    Minimal,
+    /// Encodes everything but letters and digits, including `/`, like older versions of trashy.
+    Legacy,
+}
+
+impl EncodePolicy {
+    fn ascii_set(self) -> &'static AsciiSet {
+        match self {
+            EncodePolicy::Standard => PATH_UNRESERVED,
+            EncodePolicy::Minimal => PATH_MINIMAL,
+            EncodePolicy::Legacy => NON_ALPHANUMERIC,
+        }
+    }
+}
+
+/// Percent-encodes a path or file name the way the spec expects it in trash files.
+///
+/// This is `encode_path_with` and the `Standard` policy.
+pub fn encode_path(path: &Path) -> String {
+    encode_path_with(path, EncodePolicy::Standard)
+}
+
+/// Percent-encodes a path or file name, encoding the bytes that `policy` asks for.
+pub fn encode_path_with(path: &Path, policy: EncodePolicy) -> String {
+    #[cfg(unix)]
+    let bytes = {
+        use std::os::unix::ffi::OsStrExt;
+        path.as_os_str().as_bytes().to_vec()
+    };
+    #[cfg(not(unix))]
+    let bytes = path.to_string_lossy().into_owned().into_bytes();
+    percent_encoding::percent_encode(&bytes, policy.ascii_set()).to_string()
+}
+
+/// Decodes a path that was encoded by `encode_path` or another tool.
+///
+/// Any valid percent-encoding is decoded, no matter which `EncodePolicy` wrote it.
+/// A `%` that is not followed by two hex digits is kept as it is.
+pub fn decode_path(s: &str) -> Result<PathBuf, DecodeError> {
+    let bytes = percent_encoding::percent_decode_str(s).collect::<Vec<u8>>();
+    #[cfg(unix)]
+    {
+        use std::{ffi::OsString, os::unix::ffi::OsStringExt};
+        Ok(PathBuf::from(OsString::from_vec(bytes)))
+    }
+    #[cfg(not(unix))]
+    {
+        String::from_utf8(bytes).map(PathBuf::from).map_err(|_| DecodeError { encoded: s.into() })
+    }
+}
+
+/// The error of `decode_path` when the decoded bytes are not a path on this platform.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodeError {
+    pub encoded: String,
+}
+
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} does not decode to a valid path", self.encoded)
+    }
+}
+
+impl error::Error for DecodeError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encode_path() {
+        assert_eq!(super::encode_path(Path::new("/a b/c-d_e.f~")), "/a%20b/c-d_e.f~");
+        assert_eq!(super::encode_path(Path::new("100%\n")), "100%25%0A");
+        assert_eq!(super::encode_path(Path::new("a\r\tb\x7f")), "a%0D%09b%7F");
+    }
+
+    #[test]
+    fn encode_path_with() {
+        let path = Path::new("/my files/a b%.txt");
+        for (policy, encoded) in [
+            (EncodePolicy::Standard, "/my%20files/a%20b%25.txt"),
+            (EncodePolicy::Minimal, "/my files/a b%25.txt"),
+            (EncodePolicy::Legacy, "%2Fmy%20files%2Fa%20b%25%2Etxt"),
+        ] {
+            assert_eq!(super::encode_path_with(path, policy), encoded);
+            assert_eq!(super::decode_path(encoded).unwrap(), path);
+        }
+        assert_eq!(super::encode_path_with(Path::new("a\nb"), EncodePolicy::Minimal), "a%0Ab");
+        assert_eq!(
+            super::encode_path(path),
+            super::encode_path_with(path, EncodePolicy::default())
+        );
+    }
+
+    #[test]
+    fn decode_path() {
+        assert_eq!(super::decode_path("/a%20b/c%0A"), Ok(PathBuf::from("/a b/c\n")));
+        assert_eq!(super::decode_path("100%"), Ok(PathBuf::from("100%")));
+        assert_eq!(super::decode_path("%zz"), Ok(PathBuf::from("%zz")));
+        for path in ["/a b/c-d_e.f~", "100%\n", "ünïcode"] {
+            assert_eq!(
+                super::decode_path(&super::encode_path(Path::new(path))).unwrap(),
+                Path::new(path)
+            );
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8() {
+        use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+        let path = Path::new(OsStr::from_bytes(b"/a\xffb"));
+        assert_eq!(super::encode_path(path), "/a%FFb");
+        assert_eq!(super::decode_path("/a%FFb").unwrap(), path);
+    }
+}
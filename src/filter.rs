@@ -13,32 +13,37 @@ use globset::{Glob, GlobSet, GlobSetBuilder};
 use regex::RegexSet;
 use trash::TrashItem;
 
+use crate::clock::{Clock, SystemClock};
+
 #[derive(Parser, Debug)]
 pub struct FilterArgs {
     /// Filter by time (older than)
     ///
     /// Filter results based on when the file was trashed. The argument can be provided
     /// as a specific point in time (YYYY-MM-DD HH:MM:SS) or as a duration (10h, 1d, 35min).
-    /// '--older-than' or '--older' can be used as aliases.
+    /// '--older-than', '--older', or '--until' can be used as aliases.
     /// This option can be used in 'list', 'restore', and 'empty'
     /// Examples:
     ///     --before '2018-10-27 10:00:00'
     ///     --older-than 2weeks
+    ///     --older-than 30d
     ///     --older 2018-10-27
-    #[arg(long, visible_alias = "older-than", visible_alias = "older", action = ArgAction::Append, verbatim_doc_comment)]
+    ///     --until 2018-10-28
+    #[arg(long, visible_alias = "older-than", visible_alias = "older", visible_alias = "until", action = ArgAction::Append, verbatim_doc_comment)]
     pub before: Vec<String>,
 
     /// Filter by time
     ///
     /// Filter results based on when the file was trashed. The argument can be provided
     /// as a specific point in time (YYYY-MM-DD HH:MM:SS) or as a duration (10h, 1d, 35min).
-    /// '--newer-than' or '--newer' can be used as aliases.
+    /// '--newer-than', '--newer', or '--since' can be used as aliases.
     /// This option can be used in 'list', 'restore', and 'empty'
     /// Examples:
     ///     --changed-within 2weeks
     ///     --change-newer-than '2018-10-27 10:00:00'
     ///     --newer 2018-10-27
-    #[arg(long, visible_alias = "newer-than", visible_alias = "newer", action = ArgAction::Append, verbatim_doc_comment)]
+    ///     --since 2018-10-27 --until 2018-10-28
+    #[arg(long, visible_alias = "newer-than", visible_alias = "newer", visible_alias = "since", action = ArgAction::Append, verbatim_doc_comment)]
     pub within: Vec<String>,
 
     /// Filter by regex
@@ -82,15 +87,26 @@ pub struct FilterArgs {
 
 impl FilterArgs {
     pub fn to_filters(&self) -> Result<Filters> {
-        let now = Utc::now();
-        let parse_time =
-            |s| parse_time_filter(now, s).ok_or_else(|| anyhow!("Invalid duration or date"));
+        self.to_filters_with(&SystemClock)
+    }
+
+    /// Builds the filters, taking relative times like `2weeks` as relative to `clock`.
+    pub fn to_filters_with(&self, clock: &impl Clock) -> Result<Filters> {
+        let now = clock.now_local().with_timezone(&Utc);
+        let parse_time = |s: &String| {
+            parse_time_filter(now, s).ok_or_else(|| {
+                anyhow!(
+                    "Invalid duration or date '{s}', expected a duration like 30d or 2w, \
+                     or a date like 2018-10-27"
+                )
+            })
+        };
         let mut filters = Vec::new();
         if !self.before.is_empty() {
             filters.extend(
                 self.before
                     .iter()
-                    .map(|ref s| Ok(Filter::Time(TimeFilter::Before(parse_time(s)?))))
+                    .map(|s| Ok(Filter::Time(TimeFilter::Before(parse_time(s)?))))
                     .collect::<Result<Vec<_>>>()?,
             );
         }
@@ -165,7 +181,9 @@ impl Filter {
             Filter::PatternSet(patterns) => {
                 patterns.is_match(&item.original_path().to_string_lossy())
             }
-            Filter::Time(time_filter) => time_filter.is_match(Utc.timestamp(item.time_deleted, 0)),
+            Filter::Time(time_filter) => {
+                time_filter.is_match(Utc.timestamp_opt(item.time_deleted, 0).unwrap())
+            }
             Filter::Directories(directories) => {
                 directories.iter().all(|p| item.original_path().starts_with(p))
             }
@@ -235,6 +253,9 @@ pub enum Match {
     Exact,
 }
 
+/// Returns the point in time that `s` stands for.
+///
+/// Durations like `30d`, `2w`, or `1h 30m` count back from `ref_time`.
 fn parse_time_filter(ref_time: DateTime<Utc>, s: &str) -> Option<DateTime<Utc>> {
     humantime::parse_duration(s)
         .ok()
@@ -245,11 +266,46 @@ fn parse_time_filter(ref_time: DateTime<Utc>, s: &str) -> Option<DateTime<Utc>>
                 .ok()
                 .or_else(|| {
                     NaiveDate::parse_from_str(s, "%F")
-                        .map(|nd| nd.and_hms(0, 0, 0))
+                        .map(|nd| nd.and_hms_opt(0, 0, 0))
                         .ok()
+                        .flatten()
                         .and_then(|ndt| Local.from_local_datetime(&ndt).single())
                 })
                 .or_else(|| Local.datetime_from_str(s, "%F %T").ok())
                 .map(|dt| dt.into())
         })
 }
+
+#[cfg(test)]
+mod tests {
+    use chrono::Duration;
+
+    use super::*;
+
+    #[test]
+    fn parse_time_filter() {
+        let now = Utc.with_ymd_and_hms(2023, 6, 1, 12, 0, 0).unwrap();
+        for (s, duration) in [
+            ("90s", Duration::seconds(90)),
+            ("5m", Duration::minutes(5)),
+            ("12h", Duration::hours(12)),
+            ("30d", Duration::days(30)),
+            ("2w", Duration::weeks(2)),
+            ("1h 30m", Duration::minutes(90)),
+        ] {
+            assert_eq!(super::parse_time_filter(now, s), Some(now - duration), "{s}");
+        }
+        for s in ["", "30", "d", "30x", "-2w"] {
+            assert_eq!(super::parse_time_filter(now, s), None, "{s}");
+        }
+    }
+
+    #[test]
+    fn invalid_duration() {
+        let error = match FilterArgs::parse_from(["trashy", "--older-than", "30x"]).to_filters() {
+            Ok(_) => panic!("30x is not a duration"),
+            Err(error) => error.to_string(),
+        };
+        assert!(error.contains("'30x'"), "{error}");
+    }
+}
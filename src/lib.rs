@@ -0,0 +1,11 @@
+//! Reading and writing the `.trashinfo` files of freedesktop trash directories, and the
+//! percent-encoding of the paths in them.
+//!
+//! This is the part of trashy that other tools can reuse without reimplementing the format.
+
+pub mod encoding;
+pub mod path;
+pub mod trash_info;
+
+pub use encoding::{decode_path, encode_path, encode_path_with, DecodeError, EncodePolicy};
+pub use trash_info::{parse_trash_info, ParseError, TrashInfo};
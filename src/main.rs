@@ -1,17 +1,21 @@
 mod app;
+mod clock;
 mod exitcode;
 mod filter;
 mod print;
 mod range;
 mod range_set;
 mod range_syntax;
+mod summary;
+mod trash_dir;
 mod trash_item;
+mod undo;
 mod utils;
 
 use anyhow::Result;
 use app::Args;
-use clap::Parser;
 use exitcode::ExitCode;
+use trashy::{encoding, trash_info};
 
 fn main() {
     match try_main() {
@@ -21,5 +25,5 @@ fn main() {
 }
 
 fn try_main() -> Result<()> {
-    Args::parse().run()
+    Args::parse_with_config()?.run()
 }
@@ -0,0 +1,41 @@
+//! Lexical handling of the paths in trash directories, which may no longer exist.
+
+use std::{
+    env,
+    path::{Component, Path, PathBuf},
+};
+
+/// Makes `path` absolute and lexically removes `.`, `..`, and trailing slashes.
+///
+/// Symlinks are not resolved, since the path may no longer exist.
+pub fn normalize(path: &Path) -> PathBuf {
+    let path = if path.is_relative() {
+        env::current_dir().map(|dir| dir.join(path)).unwrap_or_else(|_| path.to_path_buf())
+    } else {
+        path.to_path_buf()
+    };
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => (),
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            component => normalized.push(component),
+        }
+    }
+    normalized
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn normalize() {
+        assert_eq!(super::normalize(Path::new("/a/b/")), Path::new("/a/b"));
+        assert_eq!(super::normalize(Path::new("/a/./b/../c")), Path::new("/a/c"));
+        assert_eq!(super::normalize(Path::new("/a//b")), Path::new("/a/b"));
+        assert!(super::normalize(Path::new("a")).is_absolute());
+    }
+}
@@ -1,7 +1,11 @@
 use std::fmt;
 
-use ansi_term::Colour::Red;
+use ansi_term::Colour::{Red, Yellow};
 
 pub fn err_display(s: impl fmt::Display) {
     eprintln!("{} {s}", Red.bold().paint("error:"));
 }
+
+pub fn warn_display(s: impl fmt::Display) {
+    eprintln!("{} {s}", Yellow.bold().paint("warning:"));
+}
@@ -59,6 +59,7 @@ mod tests {
     }
 
     #[test]
+    #[allow(clippy::single_range_in_vec_init)]
     fn test_succeed() {
         parse_succeed("123..1234", [123..1234]);
         parse_succeed("7 8 4", [4..5, 7..8, 8..9]);
@@ -0,0 +1,76 @@
+use std::fmt;
+
+use crate::utils;
+
+/// A batch operation that can report an `OperationSummary`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    Put,
+    Restore,
+    Empty,
+}
+
+/// What a batch operation did, for a frontend to turn into a notification like
+/// "Moved 3 items to the trash".
+///
+/// Only the data is provided here. Showing it is up to whoever receives it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OperationSummary {
+    pub operation: Operation,
+    pub items: usize,
+    /// The total size of the items, or `None` when it is unknown, like for trash directories that
+    /// store no files that sizes can be read from.
+    pub bytes: Option<u64>,
+}
+
+impl OperationSummary {
+    /// Adds up the sizes of the items, which are unknown as soon as one of them is.
+    pub fn new(operation: Operation, sizes: impl IntoIterator<Item = Option<u64>>) -> Self {
+        let mut items = 0;
+        let mut bytes = Some(0);
+        for size in sizes {
+            items += 1;
+            bytes = bytes.zip(size).map(|(total, size)| total + size);
+        }
+        OperationSummary { operation, items, bytes }
+    }
+}
+
+impl fmt::Display for OperationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let plural = if self.items == 1 { "" } else { "s" };
+        let items = format!("{} item{plural}", self.items);
+        match self.operation {
+            Operation::Put => write!(f, "Moved {items} to the trash")?,
+            Operation::Restore => write!(f, "Restored {items}")?,
+            Operation::Empty => write!(f, "Permanently deleted {items}")?,
+        }
+        match self.bytes {
+            Some(bytes) => write!(f, " ({})", utils::human_size(bytes)),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn summary() {
+        let summary = OperationSummary::new(Operation::Put, [Some(1024), Some(1024)]);
+        assert_eq!(
+            summary,
+            OperationSummary { operation: Operation::Put, items: 2, bytes: Some(2048) }
+        );
+        assert_eq!(
+            summary.to_string(),
+            format!("Moved 2 items to the trash ({})", utils::human_size(2048))
+        );
+
+        let summary = OperationSummary::new(Operation::Empty, [Some(1), None]);
+        assert_eq!(summary.to_string(), "Permanently deleted 2 items");
+        let summary = OperationSummary::new(Operation::Restore, [Some(0)]);
+        assert_eq!(summary.to_string(), format!("Restored 1 item ({})", utils::human_size(0)));
+    }
+}
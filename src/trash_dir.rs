@@ -0,0 +1,2345 @@
+use std::{
+    collections::{HashMap, HashSet},
+    env, error,
+    ffi::{OsStr, OsString},
+    fmt, fs, io, mem,
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{bail, Context, Result};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use trash::TrashItem;
+
+use crate::{
+    encoding::{self, EncodePolicy},
+    trash_info::{TrashInfo, TRASH_INFO_EXT},
+    utils,
+};
+
+pub mod merged;
+#[cfg(feature = "watch")]
+pub mod watch;
+
+/// How much older than the cutoff an info file has to be modified for `TrashDir::read_since`
+/// to skip it, which covers deletion dates that are written in another timezone.
+const MTIME_SLACK_SECS: i64 = 24 * 60 * 60;
+
+/// How many names `TrashDir::rename_entry` tries before giving up.
+const MAX_NAME_ATTEMPTS: u32 = 1000;
+
+/// A trash directory together with the topdir that relative paths inside it are relative to.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TrashDir {
+    path: PathBuf,
+    topdir: PathBuf,
+    /// The extension of the files in `info`, which is `trashinfo` unless changed
+    info_ext: String,
+}
+
+/// An info file that could not be read.
+#[derive(Debug)]
+pub struct BrokenEntry {
+    pub info_path: PathBuf,
+    pub error: anyhow::Error,
+}
+
+/// There is not enough free space on the drive of a trash directory to copy a path into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InsufficientSpace {
+    /// The size of the path in bytes
+    pub needed: u64,
+    /// The free space in bytes
+    pub available: u64,
+}
+
+impl fmt::Display for InsufficientSpace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "Copying {} into the trash needs more than the {} that are free on its drive",
+            utils::human_size(self.needed),
+            utils::human_size(self.available)
+        )
+    }
+}
+
+impl error::Error for InsufficientSpace {}
+
+/// The `files` or `info` directory of a trash directory can't be written to, like when it is
+/// owned by another user or on a read-only mount.
+#[derive(Debug)]
+pub struct TrashNotWritable {
+    pub dir: PathBuf,
+    pub source: io::Error,
+}
+
+impl fmt::Display for TrashNotWritable {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "The trash directory '{}' is not writable. Check that it is owned by you and that it \
+             is not on a read-only mount",
+            self.dir.display()
+        )
+    }
+}
+
+impl error::Error for TrashNotWritable {
+    fn source(&self) -> Option<&(dyn error::Error + 'static)> {
+        Some(&self.source)
+    }
+}
+
+/// Fails when `needed` bytes are more than the `available` ones, unless those are unknown.
+fn check_space(needed: u64, available: Option<u64>) -> Result<(), InsufficientSpace> {
+    match available {
+        Some(available) if needed > available => Err(InsufficientSpace { needed, available }),
+        _ => Ok(()),
+    }
+}
+
+/// The reason an entry in `info` is broken when it is a directory, a symlink, or another special
+/// file instead of a regular file.
+///
+/// Symlinks are never followed, so a link in `info` can't make trashy read a file elsewhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NotARegularFile {
+    pub file_type: &'static str,
+}
+
+impl fmt::Display for NotARegularFile {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The info file is a {} instead of a regular file", self.file_type)
+    }
+}
+
+impl error::Error for NotARegularFile {}
+
+impl TrashDir {
+    /// Creates a trash directory whose info files have the extension from `$TRASHY_INFO_EXT`,
+    /// or `trashinfo` without it.
+    fn new(path: PathBuf, topdir: PathBuf) -> TrashDir {
+        let trash_dir = TrashDir { path, topdir, info_ext: TRASH_INFO_EXT.into() };
+        match info_ext_from_env() {
+            Some(ext) => trash_dir.with_info_ext(ext),
+            None => trash_dir,
+        }
+    }
+
+    /// Uses `ext` instead of `trashinfo` as the extension of the info files, for interop
+    /// experiments.
+    ///
+    /// Only the operations of `TrashDir` use the extension. Other trash tools only read
+    /// `.trashinfo` files, and so do the restoring and purging of the `trash` crate and the
+    /// `trash::delete_all` that 'trashy put' uses when it does not write the info files itself.
+    pub fn with_info_ext(self, ext: impl Into<String>) -> TrashDir {
+        TrashDir { info_ext: ext.into(), ..self }
+    }
+
+    /// Returns the path of the info file of the trashed file `name`.
+    fn info_path_of(&self, name: &OsStr) -> PathBuf {
+        let mut info_name = name.to_os_string();
+        info_name.push(".");
+        info_name.push(&self.info_ext);
+        self.path.join("info").join(info_name)
+    }
+
+    fn is_info_path(&self, path: &Path) -> bool {
+        path.extension() == Some(OsStr::new(&self.info_ext))
+    }
+
+    /// Returns the home trash if it exists.
+    pub fn home() -> Option<TrashDir> {
+        let path = home_trash().filter(|path| path.is_dir())?;
+        let topdir = topdir_of(&path, &mount_points());
+        Some(TrashDir::new(path, topdir))
+    }
+
+    /// Returns the trash directory whose info directory contains `info_path`.
+    pub fn containing(info_path: &Path) -> Option<TrashDir> {
+        let info_dir = info_path.parent()?;
+        all_trash_dirs().into_iter().find(|trash_dir| trash_dir.path.join("info") == info_dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether this is the home trash rather than the trash of a mounted drive.
+    pub fn is_home(&self) -> bool {
+        home_trash().map_or(false, |home| home == self.path)
+    }
+
+    /// Returns the absolute original path of `info`, which is read from this trash directory.
+    ///
+    /// This is how every original path is resolved, including the ones of the items that are
+    /// listed and restored.
+    pub fn original_path(&self, info: &TrashInfo) -> PathBuf {
+        self.topdir.join(info.path_decoded())
+    }
+
+    /// Reads the info file `info_path` of this trash directory and returns its absolute original
+    /// path.
+    ///
+    /// Relative paths are resolved against the topdir of the drive, so they follow the drive when
+    /// it is mounted somewhere else. This fails for a relative path when the topdir is gone,
+    /// like after the drive was unmounted, instead of putting an entry back in the wrong place.
+    pub fn original_location(&self, info_path: &Path) -> Result<PathBuf> {
+        self.resolve_original(&read_info(info_path)?)
+    }
+
+    /// Like `original_path`, but fails when the original path is relative and the topdir can't
+    /// be found anymore.
+    fn resolve_original(&self, info: &TrashInfo) -> Result<PathBuf> {
+        if info.is_relative_original() && !self.topdir.is_dir() {
+            bail!(
+                "The original path {} is relative to {}, which is not mounted. Mount the drive \
+                 again to restore it",
+                info.path_decoded().display(),
+                self.topdir.display()
+            );
+        }
+        Ok(self.original_path(info))
+    }
+
+    /// Returns the paths of the info files in this trash directory without reading them.
+    pub fn info_paths(&self) -> Vec<PathBuf> {
+        let read_dir = match fs::read_dir(self.path.join("info")) {
+            Ok(read_dir) => read_dir,
+            Err(_) => return Vec::new(),
+        };
+        read_dir
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| self.is_info_path(path))
+            .collect()
+    }
+
+    /// Renames the entry of `info_path` to `name`, or to `name.2`, `name.3`, … if that is taken.
+    ///
+    /// The file and its info file are renamed together. Returns the new info path.
+    pub fn rename_entry(&self, info_path: &Path, name: &str) -> Result<PathBuf> {
+        let current = info_path.file_stem().context("The info file does not have a name")?;
+        let (new_name, new_info_path) =
+            match self.claim_free_name(OsStr::new(name), Some(current))? {
+                Some(claimed) => claimed,
+                None => return Ok(info_path.to_path_buf()),
+            };
+        let file_path = self.path.join("files").join(current);
+        let new_file_path = self.path.join("files").join(new_name);
+        if let Err(e) = fs::rename(&file_path, &new_file_path) {
+            let _ = fs::remove_file(&new_info_path);
+            return Err(e).with_context(|| format!("Failed to rename {}", file_path.display()));
+        }
+        if let Err(e) = fs::rename(info_path, &new_info_path) {
+            // keep the file and its info file together
+            let _ = fs::rename(&new_file_path, &file_path);
+            let _ = fs::remove_file(&new_info_path);
+            return Err(e).with_context(|| format!("Failed to rename {}", info_path.display()));
+        }
+        Ok(new_info_path)
+    }
+
+    /// Reserves the first free name of `desired`, `desired.2`, `desired.3`, … in this trash
+    /// directory for a new entry.
+    ///
+    /// The trashed file has to be put at `NameClaim::file_path` before the claim is committed
+    /// with its info. Dropping a claim that was not committed removes its placeholder info file,
+    /// so no name stays taken without an entry behind it.
+    ///
+    /// A missing `files` or `info` directory is created first, like after a botched cleanup.
+    pub fn claim_name(&self, desired: impl AsRef<OsStr>) -> Result<NameClaim> {
+        let desired = desired.as_ref();
+        if desired.is_empty() || desired == "." || desired == ".." {
+            bail!("`{}` is not a file name", desired.to_string_lossy());
+        }
+        if desired.to_string_lossy().contains('/') {
+            bail!("The name `{}` must not contain path separators", desired.to_string_lossy());
+        }
+        if self.health() != TrashHealth::Healthy {
+            create_trash_dir(&self.path, DirMode::from_env()?)?;
+        }
+        let encode_policy = encode_policy_from_env()?;
+        let (name, info_path) =
+            self.claim_free_name(desired, None)?.expect("BUG: no current name was given");
+        Ok(NameClaim {
+            file_path: self.path.join("files").join(name),
+            info_path,
+            encode_policy,
+            committed: false,
+        })
+    }
+
+    /// Claims the first free name of `name`, `name.2`, `name.3`, … in this trash directory.
+    ///
+    /// Like the spec asks for, a name is claimed by creating its info file exclusively, so a name
+    /// that another process takes in the meantime is skipped instead of overwritten.
+    /// Returns the name and its empty info file, or `None` when `current` comes up first.
+    fn claim_free_name(
+        &self,
+        name: &OsStr,
+        current: Option<&OsStr>,
+    ) -> Result<Option<(OsString, PathBuf)>> {
+        for n in 1..=MAX_NAME_ATTEMPTS {
+            let mut candidate = name.to_os_string();
+            if n > 1 {
+                candidate.push(format!(".{n}"));
+            }
+            if current == Some(candidate.as_os_str()) {
+                return Ok(None);
+            }
+            if self.path.join("files").join(&candidate).symlink_metadata().is_ok() {
+                continue;
+            }
+            let info_path = self.info_path_of(&candidate);
+            match fs::OpenOptions::new().write(true).create_new(true).open(&info_path) {
+                Ok(_) => return Ok(Some((candidate, info_path))),
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => continue,
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to create {}", info_path.display()))
+                }
+            }
+        }
+        bail!(
+            "Could not find a free name for {} after {MAX_NAME_ATTEMPTS} attempts",
+            name.to_string_lossy()
+        )
+    }
+
+    /// Trashes `path` into this trash directory by copying it and then removing the original.
+    ///
+    /// This works across filesystems, unlike the rename that trashing normally uses, but it is
+    /// slower and the copy does not keep ownership. Extended attributes are kept with the `xattr`
+    /// feature, see `utils::path::copy_all`. Nothing is copied when the drive of this trash
+    /// directory has less free space than `path` needs. Returns the new info path.
+    pub fn put_by_copy(&self, path: &Path, deletion_date: NaiveDateTime) -> Result<PathBuf> {
+        let absolute = utils::path::canonicalize_parent(path).with_context(|| {
+            format!("Failed to resolve the parent directory of {}", path.display())
+        })?;
+        let name = absolute.file_name().context("The Path does not have a file name")?;
+        let needed = utils::path::size(path)
+            .with_context(|| format!("Failed to read the size of {}", path.display()))?;
+        check_space(needed, available_space(&self.path))?;
+        let claim = self.claim_name(name)?;
+        if let Err(e) = utils::path::copy_all(path, claim.file_path()) {
+            let _ = utils::path::remove_all(claim.file_path());
+            return Err(e).with_context(|| format!("Failed to copy {}", path.display()));
+        }
+        // the original path is written absolute, since it is not below the topdir of this trash
+        let info_path = claim.commit(&TrashInfo::new(&absolute, deletion_date))?;
+        utils::path::remove_all(path)
+            .with_context(|| format!("Failed to remove {} after copying it", path.display()))?;
+        Ok(info_path)
+    }
+
+    /// Trashes the regular file `path` by hardlinking it into this trash directory and then
+    /// unlinking the original.
+    ///
+    /// The file has to be on the same filesystem as this trash directory. Returns the new info
+    /// path.
+    pub fn put_by_hardlink(&self, path: &Path, deletion_date: NaiveDateTime) -> Result<PathBuf> {
+        let absolute = utils::path::canonicalize_parent(path).with_context(|| {
+            format!("Failed to resolve the parent directory of {}", path.display())
+        })?;
+        let name = absolute.file_name().context("The Path does not have a file name")?;
+        let claim = self.claim_name(name)?;
+        fs::hard_link(path, claim.file_path())
+            .with_context(|| format!("Failed to hardlink {}", path.display()))?;
+        let info_path = claim.commit(&TrashInfo::new(&absolute, deletion_date))?;
+        fs::remove_file(path)
+            .with_context(|| format!("Failed to remove {} after linking it", path.display()))?;
+        Ok(info_path)
+    }
+
+    /// Moves the entry of `info_path` into the trash directory `dest`.
+    ///
+    /// The entry is copied before it is removed here, so it can't be lost when this is interrupted.
+    /// Every key of the info file is kept, only the original path is rewritten.
+    fn migrate_entry(&self, info_path: &Path, dest: &TrashDir) -> Result<()> {
+        let info = read_info(info_path)?;
+        let original_path = self.original_path(&info);
+        let name = info_path.file_stem().context("The info file does not have a name")?;
+        let file_path = self.path.join("files").join(name);
+        if file_path.symlink_metadata().is_err() {
+            bail!("The trashed file {} does not exist", file_path.display());
+        }
+        let claim = dest.claim_name(name)?;
+        let dest_path = claim.file_path().to_path_buf();
+        let moved = fs::rename(&file_path, &dest_path).is_ok();
+        if !moved {
+            if let Err(e) = utils::path::copy_all(&file_path, &dest_path) {
+                let _ = utils::path::remove_all(&dest_path);
+                return Err(e).with_context(|| format!("Failed to copy {}", file_path.display()));
+            }
+        }
+        // the original path is written absolute, since `dest` may have another topdir
+        if let Err(e) = claim.commit(&info.with_path(&original_path)) {
+            // put the file back, so the info file here does not point at a missing file
+            if moved {
+                let _ = fs::rename(&dest_path, &file_path);
+            } else {
+                let _ = utils::path::remove_all(&dest_path);
+            }
+            return Err(e);
+        }
+        fs::remove_file(info_path)
+            .with_context(|| format!("Failed to remove {}", info_path.display()))?;
+        if !moved {
+            utils::path::remove_all(&file_path)
+                .with_context(|| format!("Failed to remove {}", file_path.display()))?;
+        }
+        Ok(())
+    }
+
+    /// Restores every entry of this trash directory below `new_root` instead of at its original
+    /// path, keeping the whole original path. For example `/home/a/x` is restored to
+    /// `new_root/home/a/x`, creating the directories in between.
+    ///
+    /// Nothing is overwritten: an entry whose new path is taken stays in the trash and is reported
+    /// as failed, like entries that can't be read or moved.
+    pub fn restore_all_under(&self, new_root: &Path) -> Result<RestoreReport> {
+        if is_inside_trash(new_root, std::slice::from_ref(self)) {
+            bail!("Cannot restore into {}, since it is inside the trash", new_root.display());
+        }
+        let mut report = RestoreReport::default();
+        for info_path in self.info_paths() {
+            match self.restore_entry_under(&info_path, new_root) {
+                Ok(path) => report.restored.push(path),
+                Err(error) => report.failed.push(BrokenEntry { info_path, error }),
+            }
+        }
+        if !report.restored.is_empty() {
+            self.rebuild_directorysizes()?;
+        }
+        Ok(report)
+    }
+
+    /// Moves the entry of `info_path` to its original path below `new_root` and returns that path.
+    fn restore_entry_under(&self, info_path: &Path, new_root: &Path) -> Result<PathBuf> {
+        let info = read_info(info_path)?;
+        // `..` can't lead out of `new_root`, since it is resolved before the root is dropped
+        let original_path = utils::path::normalize(&self.resolve_original(&info)?);
+        let relative: PathBuf = original_path
+            .components()
+            .filter(|component| matches!(component, Component::Normal(_)))
+            .collect();
+        if relative.as_os_str().is_empty() {
+            bail!("The original path {} has no file name", original_path.display());
+        }
+        let dest = new_root.join(relative);
+        let name = info_path.file_stem().context("The info file does not have a name")?;
+        let file_path = self.path.join("files").join(name);
+        if file_path.symlink_metadata().is_err() {
+            bail!("The trashed file {} does not exist", file_path.display());
+        }
+        if dest.symlink_metadata().is_ok() {
+            bail!("{} already exists", dest.display());
+        }
+        if let Some(parent) = dest.parent() {
+            fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let moved = fs::rename(&file_path, &dest).is_ok();
+        if !moved {
+            if let Err(e) = utils::path::copy_all(&file_path, &dest) {
+                let _ = utils::path::remove_all(&dest);
+                return Err(e).with_context(|| format!("Failed to copy {}", file_path.display()));
+            }
+        }
+        fs::remove_file(info_path)
+            .with_context(|| format!("Failed to remove {}", info_path.display()))?;
+        if !moved {
+            utils::path::remove_all(&file_path)
+                .with_context(|| format!("Failed to remove {}", file_path.display()))?;
+        }
+        Ok(dest)
+    }
+
+    /// Moves info files and trashed files that were left in the root of this trash directory,
+    /// like some tools do, into `info` and `files`.
+    ///
+    /// A name that is taken already gets a number appended, like when trashing. Trashed files
+    /// without an info file are moved into `files` too, where they are orphans. Info files that
+    /// can't be read or have no trashed file are left where they are and reported as failed.
+    pub fn import_legacy(&self) -> Result<ImportReport> {
+        let mut report = ImportReport::default();
+        let read_dir = match fs::read_dir(&self.path) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(report),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()))
+            }
+        };
+        let mut info_paths = Vec::new();
+        let mut loose = Vec::new();
+        for entry in read_dir {
+            let path = entry?.path();
+            let name = path.file_name().unwrap_or_default();
+            if LAYOUT_NAMES.iter().any(|known| name == *known) {
+                continue;
+            }
+            if self.is_info_path(&path) && path.is_file() {
+                info_paths.push(path);
+            } else {
+                loose.push(path);
+            }
+        }
+        if info_paths.is_empty() && loose.is_empty() {
+            return Ok(report);
+        }
+        for dir in [self.path.join("files"), self.path.join("info")] {
+            fs::create_dir_all(&dir)
+                .with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        info_paths.sort();
+        for info_path in info_paths {
+            let file_path = info_path.with_extension("");
+            let imported = if file_path.symlink_metadata().is_ok() {
+                loose.retain(|path| *path != file_path);
+                self.import_legacy_entry(&info_path, &file_path)
+            } else {
+                Err(anyhow::anyhow!("There is no trashed file {}", file_path.display()))
+            };
+            match imported {
+                Ok(new_info_path) => {
+                    report.imported += 1;
+                    if new_info_path.file_stem() != info_path.file_stem() {
+                        report.renamed.push((info_path, new_info_path));
+                    }
+                }
+                Err(error) => report.failed.push(BrokenEntry { info_path, error }),
+            }
+        }
+        loose.sort();
+        for path in loose {
+            let name = path.file_name().context("Invalid trashed file path")?;
+            // the claim only reserves the name and is dropped, since the original path is unknown
+            let claim = self.claim_name(name)?;
+            fs::rename(&path, claim.file_path())
+                .with_context(|| format!("Failed to move {}", path.display()))?;
+            report.orphaned.push(claim.file_path().to_path_buf());
+        }
+        Ok(report)
+    }
+
+    /// Moves the misplaced `info_path` and `file_path` into `info` and `files`.
+    ///
+    /// Returns the new info path.
+    fn import_legacy_entry(&self, info_path: &Path, file_path: &Path) -> Result<PathBuf> {
+        let info = read_info(info_path)?;
+        let name = file_path.file_name().context("Invalid trashed file path")?;
+        let claim = self.claim_name(name)?;
+        fs::rename(file_path, claim.file_path())
+            .with_context(|| format!("Failed to move {}", file_path.display()))?;
+        let new_info_path = claim.commit(&info)?;
+        fs::remove_file(info_path)
+            .with_context(|| format!("Failed to remove {}", info_path.display()))?;
+        Ok(new_info_path)
+    }
+
+    /// Counts the entries in this trash directory by the directory they were trashed from.
+    ///
+    /// Only the paths of the info files are used, so this is cheaper than reading the items.
+    /// Info files that can't be read are left out.
+    pub fn count_by_parent(&self) -> Result<HashMap<PathBuf, usize>> {
+        let mut counts = HashMap::new();
+        for info_path in self.info_paths() {
+            let info = match read_info(&info_path) {
+                Ok(info) => info,
+                Err(_) => continue,
+            };
+            if let Some(parent) = self.original_path(&info).parent() {
+                *counts.entry(parent.to_path_buf()).or_insert(0) += 1;
+            }
+        }
+        Ok(counts)
+    }
+
+    /// Returns the paths that restoring the entry of `info_path` would overwrite.
+    ///
+    /// For a trashed file this is at most its original path. For a trashed directory that
+    /// would land on an existing directory, both trees are walked and every path inside that
+    /// exists on both sides is returned, unless it is a directory on both sides, which is then
+    /// walked further. Paths are returned in the order they are found.
+    pub fn restore_conflicts(&self, info_path: &Path) -> Result<Vec<PathBuf>> {
+        let name = info_path.file_stem().context("The info file does not have a name")?;
+        let files_path = self.path.join("files").join(name);
+        let mut conflicts = Vec::new();
+        collect_conflicts(&files_path, &self.original_location(info_path)?, &mut conflicts)
+            .with_context(|| format!("Failed to read {}", files_path.display()))?;
+        Ok(conflicts)
+    }
+
+    /// Checks that the trashed file of every entry can still be read, returning the ones that
+    /// can't.
+    ///
+    /// Directories are walked, and every regular file in them is opened. With `full_read`,
+    /// regular files are also read to the end, which finds errors of a failing drive that opening
+    /// does not, but takes as long as reading the whole trash. Symlinks are not followed, and
+    /// other special files are not opened, since opening a fifo would block.
+    pub fn verify_readable(&self, full_read: bool) -> Result<Vec<UnreadableEntry>> {
+        let mut unreadable = Vec::new();
+        for info_path in self.info_paths() {
+            let name = info_path.file_stem().context("The info file does not have a name")?;
+            let files_path = self.path.join("files").join(name);
+            if let Err((path, error)) = verify_readable(&files_path, full_read) {
+                unreadable.push(UnreadableEntry { info_path, path, error });
+            }
+        }
+        Ok(unreadable)
+    }
+
+    /// Returns the files in this trash directory that have no info file.
+    ///
+    /// These are left behind when the info directory is lost, and their original path is unknown.
+    pub fn orphans(&self) -> Vec<PathBuf> {
+        let read_dir = match fs::read_dir(self.path.join("files")) {
+            Ok(read_dir) => read_dir,
+            Err(_) => return Vec::new(),
+        };
+        read_dir
+            .flatten()
+            .filter(|entry| fs::symlink_metadata(self.info_path_of(&entry.file_name())).is_err())
+            .map(|entry| entry.path())
+            .collect()
+    }
+
+    /// Whether this trash directory has no items, which stops at the first info file found.
+    pub fn is_empty(&self) -> Result<bool> {
+        let read_dir = match fs::read_dir(self.path.join("info")) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(true),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", self.path.display()))
+            }
+        };
+        for entry in read_dir {
+            if self.is_info_path(&entry?.path()) {
+                return Ok(false);
+            }
+        }
+        Ok(true)
+    }
+
+    /// Recomputes the `directorysizes` cache from the directories in this trash directory.
+    ///
+    /// Lines of entries that are gone are dropped, and the file is replaced atomically.
+    pub fn rebuild_directorysizes(&self) -> Result<()> {
+        let mut contents = String::new();
+        let read_dir = match fs::read_dir(self.path.join("files")) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+            Err(e) => return Err(e).context("Failed to read the trashed files"),
+        };
+        for entry in read_dir {
+            let entry = entry?;
+            if !entry.file_type()?.is_dir() {
+                continue;
+            }
+            // the spec uses the modification time of the info file to detect outdated lines
+            let mtime = match fs::metadata(self.info_path_of(&entry.file_name()))
+                .and_then(|meta| meta.modified())
+            {
+                Ok(mtime) => unix_secs(mtime),
+                Err(_) => continue,
+            };
+            let size = utils::path::size(&entry.path())?;
+            let name = encoding::encode_path(Path::new(&entry.file_name()));
+            contents.push_str(&format!("{size} {mtime} {name}\n"));
+        }
+        let path = self.path.join("directorysizes");
+        let tmp_path = self.path.join("directorysizes.tmp");
+        fs::write(&tmp_path, contents)
+            .and_then(|()| fs::rename(&tmp_path, &path))
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Returns the directories whose line in the `directorysizes` cache is out of date.
+    ///
+    /// A line is out of date when its directory is gone, its info file was changed since the line
+    /// was written, or the size no longer matches. Nothing is written, see
+    /// `rebuild_directorysizes` for fixing the cache.
+    pub fn stale_cache_entries(&self) -> Result<Vec<PathBuf>> {
+        let path = self.path.join("directorysizes");
+        let contents = match fs::read_to_string(&path) {
+            Ok(contents) => contents,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let mut stale = Vec::new();
+        for line in contents.lines() {
+            let mut fields = line.splitn(3, ' ');
+            let (size, mtime, name) = match (fields.next(), fields.next(), fields.next()) {
+                (Some(size), Some(mtime), Some(name)) => (size, mtime, name),
+                _ => continue,
+            };
+            let name = match encoding::decode_path(name) {
+                Ok(name) => name,
+                Err(_) => continue,
+            };
+            let files_path = self.path.join("files").join(&name);
+            let info_mtime = fs::metadata(self.info_path_of(name.as_os_str()))
+                .and_then(|meta| meta.modified())
+                .map(unix_secs);
+            let is_current = files_path.is_dir()
+                && info_mtime.map_or(false, |info_mtime| mtime == info_mtime.to_string())
+                && utils::path::size(&files_path)
+                    .map_or(false, |actual| size == actual.to_string());
+            if !is_current {
+                stale.push(files_path);
+            }
+        }
+        stale.sort();
+        Ok(stale)
+    }
+
+    /// Tells whether the `files` and `info` directories of this trash directory exist.
+    pub fn health(&self) -> TrashHealth {
+        match (self.path.join("files").is_dir(), self.path.join("info").is_dir()) {
+            (true, true) => TrashHealth::Healthy,
+            (false, true) => TrashHealth::MissingFiles,
+            (true, false) => TrashHealth::MissingInfo,
+            (false, false) => TrashHealth::Empty,
+        }
+    }
+
+    /// Returns every info file as broken when the `files` directory is missing, since none of
+    /// them has a trashed file to restore then. `entries` does the same while reading lazily.
+    fn broken_without_files(&self) -> Option<Vec<BrokenEntry>> {
+        if self.health() != TrashHealth::MissingFiles {
+            return None;
+        }
+        Some(
+            self.info_paths()
+                .into_iter()
+                .map(|info_path| BrokenEntry { info_path, error: missing_files_error() })
+                .collect(),
+        )
+    }
+
+    /// Reads the items in this trash directory.
+    ///
+    /// Info files that can't be read don't stop the listing, they are returned separately instead.
+    /// So are all info files when the `files` directory is missing.
+    pub fn read(&self) -> (Vec<TrashItem>, Vec<BrokenEntry>) {
+        let mut items = Vec::new();
+        let mut broken = Vec::new();
+        for entry in self.entries() {
+            match entry {
+                Ok(item) => items.push(item),
+                Err(entry) => broken.push(entry),
+            }
+        }
+        (items, broken)
+    }
+
+    /// Reads the items in this trash directory that were trashed at or after `cutoff`, in seconds
+    /// since the unix epoch.
+    ///
+    /// Parsing every info file is slow in a large trash, so the modification time of an info
+    /// file is used as a stand-in for its deletion date first: an info file is written when its
+    /// item is trashed, so one that was last modified more than a day before `cutoff` is skipped
+    /// without reading it. The items that are left are then checked against their parsed
+    /// deletion date. An info file that was rewritten later, like by 'put --deletion-date', is only
+    /// read for nothing. But an item is wrongly left out when its info file has an older
+    /// modification time than its deletion date, like when it was copied from another trash with
+    /// its modification time set to an older one, or when the clock was wrong while trashing.
+    pub fn read_since(&self, cutoff: i64) -> (Vec<TrashItem>, Vec<BrokenEntry>) {
+        if let Some(broken) = self.broken_without_files() {
+            return (Vec::new(), broken);
+        }
+        let mut items = Vec::new();
+        let mut broken = Vec::new();
+        for info_path in self.info_paths() {
+            let modified = fs::symlink_metadata(&info_path).and_then(|meta| meta.modified());
+            if matches!(modified, Ok(mtime) if (unix_secs(mtime) as i64) < cutoff - MTIME_SLACK_SECS)
+            {
+                continue;
+            }
+            match self.read_item(&info_path) {
+                Ok(item) if item.time_deleted >= cutoff => items.push(item),
+                Ok(_) => (),
+                Err(error) => broken.push(BrokenEntry { info_path, error }),
+            }
+        }
+        (items, broken)
+    }
+
+    /// Reads the items in this trash directory whose original file name is `name`.
+    ///
+    /// Files with the same name from different directories all match, so the result can have
+    /// more than one item even when each was trashed once. Info files that can't be read are
+    /// skipped.
+    pub fn find_by_basename(&self, name: &str) -> Vec<TrashItem> {
+        let mut items = self.read().0;
+        items.retain(|item| item.name == name);
+        items
+    }
+
+    /// Reads the item of a single info file in this trash directory.
+    pub fn read_item(&self, info_path: &Path) -> Result<TrashItem> {
+        let info = read_info(info_path)?;
+        let original_path = self.original_path(&info);
+        let name = original_path.file_name().context("The Path does not have a file name")?;
+        let original_parent = original_path.parent().context("The Path does not have a parent")?;
+        let time_deleted = Local
+            .from_local_datetime(&info.deletion_date())
+            .earliest()
+            .context("The DeletionDate does not exist in the local timezone")?
+            .timestamp();
+        Ok(TrashItem {
+            id: info_path.into(),
+            name: name.to_string_lossy().into_owned(),
+            original_parent: original_parent.to_path_buf(),
+            time_deleted,
+        })
+    }
+
+    /// Counts the items that emptying this trash directory deletes and how much space they use.
+    ///
+    /// Nothing is deleted. Like `empty`, entries whose info file can't be read are left out,
+    /// and the sizes of directories are read from the `directorysizes` cache when it is current.
+    pub fn empty_preview(&self) -> Result<EmptyReport> {
+        let mut report = EmptyReport::default();
+        for item in self.read().0 {
+            let info_path = Path::new(&item.id);
+            let name = info_path.file_stem().context("Invalid info path")?;
+            let files_path = self.path.join("files").join(name);
+            report.items += 1;
+            report.size += match fs::symlink_metadata(&files_path) {
+                Ok(meta) => self.files_size(&files_path, &meta)?,
+                Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+                Err(e) => {
+                    return Err(e)
+                        .with_context(|| format!("Failed to read {}", files_path.display()))
+                }
+            };
+        }
+        Ok(report)
+    }
+
+    /// Returns how many bytes the trashed files in this trash directory use, which is what
+    /// emptying it frees.
+    ///
+    /// Only what is in `files` is counted, including orphans, and not the info files, the
+    /// `directorysizes` cache, or anything else in the trash directory. The sizes of directories
+    /// are read from the `directorysizes` cache when it is current.
+    pub fn used_space(&self) -> Result<u64> {
+        let files = self.path.join("files");
+        let read_dir = match fs::read_dir(&files) {
+            Ok(read_dir) => read_dir,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", files.display())),
+        };
+        let mut used = 0;
+        for entry in read_dir {
+            let entry = entry?;
+            let meta = fs::symlink_metadata(entry.path())?;
+            used += self.files_size(&entry.path(), &meta)?;
+        }
+        Ok(used)
+    }
+
+    /// Returns the size of `files_path` in the `files` directory, with `meta` being its metadata.
+    fn files_size(&self, files_path: &Path, meta: &fs::Metadata) -> Result<u64> {
+        let cached = meta
+            .is_dir()
+            .then(|| {
+                let info_path = self.info_path_of(files_path.file_name()?);
+                let mtime = fs::metadata(&info_path).and_then(|meta| meta.modified()).ok()?;
+                cached_directory_size(&info_path, mtime)
+            })
+            .flatten();
+        match cached {
+            Some(size) => Ok(size),
+            None => utils::path::size(files_path)
+                .with_context(|| format!("Failed to read {}", files_path.display())),
+        }
+    }
+
+    /// Returns the info path of the entry that is stored as `name` in the files directory.
+    ///
+    /// Names that are not a single path component never match.
+    pub fn find_by_trashed_name(&self, name: &str) -> Option<PathBuf> {
+        if Path::new(name).file_name() != Some(OsStr::new(name)) {
+            return None;
+        }
+        let info_path = self.info_path_of(OsStr::new(name));
+        info_path.is_file().then_some(info_path)
+    }
+
+    /// Permanently deletes the entries stored as `names`.
+    ///
+    /// Names without an entry are skipped and reported as not found.
+    pub fn purge_by_names(&self, names: &[&str]) -> Result<PurgeReport> {
+        let mut report = PurgeReport::default();
+        for &name in names {
+            let info_path = match self.find_by_trashed_name(name) {
+                Some(info_path) => info_path,
+                None => {
+                    report.not_found.push(name.to_owned());
+                    continue;
+                }
+            };
+            self.purge_entry(&info_path)?;
+            report.purged += 1;
+        }
+        Ok(report)
+    }
+
+    /// Permanently deletes every entry that was trashed from `path`.
+    ///
+    /// Both paths are compared after lexically removing `.` and `..`, so `path` has to be
+    /// absolute like the original paths are. A file that was trashed several times has several
+    /// entries, which are all deleted and counted. When no entry was trashed from `path`, it is
+    /// reported as not found.
+    pub fn purge_by_original(&self, path: &Path) -> Result<PurgeReport> {
+        let path = utils::path::normalize(path);
+        let mut report = PurgeReport::default();
+        for info_path in self.info_paths() {
+            match self.original_location(&info_path) {
+                Ok(original) if utils::path::normalize(&original) == path => {
+                    self.purge_entry(&info_path)?;
+                    report.purged += 1;
+                }
+                _ => (),
+            }
+        }
+        if report.purged == 0 {
+            report.not_found.push(path.to_string_lossy().into_owned());
+        }
+        Ok(report)
+    }
+
+    /// Deletes the file and the info file of the entry of `info_path`.
+    fn purge_entry(&self, info_path: &Path) -> Result<()> {
+        let name = info_path.file_stem().context("The info file does not have a name")?;
+        let file_path = self.path.join("files").join(name);
+        match utils::path::remove_all(&file_path) {
+            Ok(()) => {}
+            // the info file is still worth removing when its file is already gone
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to delete {}", file_path.display()))
+            }
+        }
+        fs::remove_file(info_path)
+            .with_context(|| format!("Failed to delete {}", info_path.display()))
+    }
+}
+
+/// Which of the `files` and `info` directories of a trash directory exist, from
+/// `TrashDir::health`.
+///
+/// Without `info`, every trashed file is an orphan. Without `files`, every info file is broken.
+/// Trashing into the trash directory creates the missing one again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrashHealth {
+    Healthy,
+    MissingFiles,
+    MissingInfo,
+    /// Neither exists, like in a trash directory that was never used
+    Empty,
+}
+
+/// What emptying a trash directory would delete, as estimated by `TrashDir::empty_preview`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct EmptyReport {
+    pub items: usize,
+    /// The total size in bytes.
+    pub size: u64,
+}
+
+/// The names in the root of a trash directory that belong there, which
+/// `TrashDir::import_legacy` leaves alone.
+const LAYOUT_NAMES: &[&str] =
+    &["files", "info", "expunged", "directorysizes", "directorysizes.tmp", "metadata"];
+
+/// What `TrashDir::import_legacy` did.
+#[derive(Debug, Default)]
+pub struct ImportReport {
+    /// How many entries were moved together with their info file
+    pub imported: usize,
+    /// The old and new info paths of the entries whose name was taken
+    pub renamed: Vec<(PathBuf, PathBuf)>,
+    /// The new paths of the trashed files that had no info file
+    pub orphaned: Vec<PathBuf>,
+    pub failed: Vec<BrokenEntry>,
+}
+
+/// Adds the paths below `dest` that restoring `trashed` to it would overwrite to `conflicts`.
+fn collect_conflicts(trashed: &Path, dest: &Path, conflicts: &mut Vec<PathBuf>) -> io::Result<()> {
+    let existing = match fs::symlink_metadata(dest) {
+        Ok(meta) => meta,
+        Err(_) => return Ok(()),
+    };
+    if !existing.is_dir() || !fs::symlink_metadata(trashed)?.is_dir() {
+        conflicts.push(dest.to_path_buf());
+        return Ok(());
+    }
+    let mut entries = fs::read_dir(trashed)?.collect::<io::Result<Vec<_>>>()?;
+    entries.sort_by_key(|entry| entry.file_name());
+    for entry in entries {
+        collect_conflicts(&entry.path(), &dest.join(entry.file_name()), conflicts)?;
+    }
+    Ok(())
+}
+
+/// An entry whose trashed file can't be read, from `TrashDir::verify_readable`.
+#[derive(Debug)]
+pub struct UnreadableEntry {
+    pub info_path: PathBuf,
+    /// The file that failed, which is inside the trashed file when that is a directory
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+/// Reads `path` and everything in it, returning the first path that fails.
+fn verify_readable(path: &Path, full_read: bool) -> Result<(), (PathBuf, io::Error)> {
+    let fail = |error| (path.to_path_buf(), error);
+    let meta = fs::symlink_metadata(path).map_err(fail)?;
+    if meta.is_dir() {
+        for entry in fs::read_dir(path).map_err(fail)? {
+            verify_readable(&entry.map_err(fail)?.path(), full_read)?;
+        }
+    } else if meta.file_type().is_symlink() {
+        fs::read_link(path).map_err(fail)?;
+    } else if meta.is_file() {
+        let mut file = fs::File::open(path).map_err(fail)?;
+        if full_read {
+            io::copy(&mut file, &mut io::sink()).map_err(fail)?;
+        }
+    }
+    Ok(())
+}
+
+/// What `TrashDir::purge_by_names` or `TrashDir::purge_by_original` did.
+#[derive(Debug, Default)]
+pub struct PurgeReport {
+    pub purged: usize,
+    pub not_found: Vec<String>,
+}
+
+/// A name in a trash directory that is reserved by an empty info file, from
+/// `TrashDir::claim_name`.
+#[derive(Debug)]
+pub struct NameClaim {
+    file_path: PathBuf,
+    info_path: PathBuf,
+    /// How the original path is encoded when the claim is committed
+    encode_policy: EncodePolicy,
+    committed: bool,
+}
+
+impl NameClaim {
+    /// Where the trashed file has to be put before the claim is committed.
+    pub fn file_path(&self) -> &Path {
+        &self.file_path
+    }
+
+    /// Writes `info` into the placeholder, which makes the claim a trash entry.
+    ///
+    /// The original path is encoded with the policy of `$TRASHY_PATH_ENCODING`. Fails without
+    /// keeping the name when nothing was put at `file_path`. Returns the info path.
+    pub fn commit(mut self, info: &TrashInfo) -> Result<PathBuf> {
+        if self.file_path.symlink_metadata().is_err() {
+            bail!("Nothing was put at {} to commit", self.file_path.display());
+        }
+        write_info(&self.info_path, &info.clone().with_encode_policy(self.encode_policy))?;
+        self.committed = true;
+        Ok(mem::take(&mut self.info_path))
+    }
+}
+
+impl Drop for NameClaim {
+    fn drop(&mut self) {
+        if !self.committed {
+            let _ = fs::remove_file(&self.info_path);
+        }
+    }
+}
+
+/// Returns the size of the trashed directory of `info_path` from the `directorysizes` cache.
+///
+/// Lines are only used while `info_mtime` matches the modification time they were written with.
+pub fn cached_directory_size(info_path: &Path, info_mtime: SystemTime) -> Option<u64> {
+    let trash = info_path.parent()?.parent()?;
+    let name = encoding::encode_path(Path::new(info_path.file_stem()?));
+    let mtime = unix_secs(info_mtime).to_string();
+    let contents = fs::read_to_string(trash.join("directorysizes")).ok()?;
+    contents.lines().find_map(|line| {
+        let mut fields = line.splitn(3, ' ');
+        let size = fields.next()?;
+        (fields.next()? == mtime && fields.next()? == name).then(|| size.parse().ok())?
+    })
+}
+
+fn unix_secs(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+/// Whether all of `trash_dirs` are empty, which stops at the first one that is not.
+pub fn all_empty(trash_dirs: &[TrashDir]) -> Result<bool> {
+    for trash_dir in trash_dirs {
+        if !trash_dir.is_empty()? {
+            return Ok(false);
+        }
+    }
+    Ok(true)
+}
+
+/// What `TrashDir::restore_all_under` did.
+#[derive(Debug, Default)]
+pub struct RestoreReport {
+    /// The paths below the new root that entries were restored to
+    pub restored: Vec<PathBuf>,
+    pub failed: Vec<BrokenEntry>,
+}
+
+/// What `migrate_home_trash` did.
+#[derive(Debug, Default)]
+pub struct MigrationReport {
+    pub moved: usize,
+    pub failed: Vec<BrokenEntry>,
+}
+
+/// Moves every entry of the home trash into the trash directory `new_root`, which is created
+/// with `mode` if needed.
+///
+/// Entries that can't be moved stay in the home trash and are reported as failed.
+pub fn migrate_home_trash(new_root: &Path, mode: DirMode) -> Result<MigrationReport> {
+    let home = TrashDir::home().context("There is no home trash")?;
+    let new_root = utils::path::normalize(new_root);
+    if is_inside_trash(&new_root, std::slice::from_ref(&home)) {
+        bail!(
+            "Cannot move the home trash to {}, which is the home trash or inside it",
+            new_root.display()
+        );
+    }
+    create_trash_dir(&new_root, mode)?;
+    let topdir = topdir_of(&new_root, &mount_points());
+    let dest = TrashDir::new(new_root, topdir);
+    let mut report = MigrationReport::default();
+    for info_path in home.info_paths() {
+        match home.migrate_entry(&info_path, &dest) {
+            Ok(()) => report.moved += 1,
+            Err(error) => report.failed.push(BrokenEntry { info_path, error }),
+        }
+    }
+    home.rebuild_directorysizes()?;
+    dest.rebuild_directorysizes()?;
+    Ok(report)
+}
+
+/// The permissions of the trash directories that trashy creates.
+///
+/// The spec asks for trash directories to only be accessible by their owner, so the default is
+/// `0700`. Setting `$TRASHY_DIR_MODE` to an octal mode like `755` overrides it for shared setups.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DirMode(pub u32);
+
+impl Default for DirMode {
+    fn default() -> Self {
+        DirMode(0o700)
+    }
+}
+
+impl DirMode {
+    pub const ENV_VAR: &'static str = "TRASHY_DIR_MODE";
+
+    pub fn from_env() -> Result<DirMode> {
+        match env::var(Self::ENV_VAR) {
+            Ok(mode) => u32::from_str_radix(mode.trim(), 8)
+                .ok()
+                .filter(|mode| *mode <= 0o7777)
+                .map(DirMode)
+                .with_context(|| format!("${} must be an octal mode, not `{mode}`", Self::ENV_VAR)),
+            Err(_) => Ok(DirMode::default()),
+        }
+    }
+}
+
+/// The environment variable that picks how trashy encodes the original paths it writes.
+pub const PATH_ENCODING_ENV_VAR: &str = "TRASHY_PATH_ENCODING";
+
+/// Reads the `EncodePolicy` from `$TRASHY_PATH_ENCODING`, which is `standard`, `minimal`, or
+/// `legacy`.
+///
+/// Without it, paths are encoded like trash-cli does. The other policies are for keeping the
+/// encoding of a trash that other tools already wrote one way.
+pub fn encode_policy_from_env() -> Result<EncodePolicy> {
+    match env::var(PATH_ENCODING_ENV_VAR) {
+        Ok(policy) => match policy.trim() {
+            "standard" => Ok(EncodePolicy::Standard),
+            "minimal" => Ok(EncodePolicy::Minimal),
+            "legacy" => Ok(EncodePolicy::Legacy),
+            policy => bail!(
+                "${PATH_ENCODING_ENV_VAR} must be standard, minimal, or legacy, not `{policy}`"
+            ),
+        },
+        Err(_) => Ok(EncodePolicy::default()),
+    }
+}
+
+/// The environment variable that changes the extension of the info files in trash directories.
+pub const INFO_EXT_ENV_VAR: &str = "TRASHY_INFO_EXT";
+
+/// Reads the extension of the info files from `$TRASHY_INFO_EXT`, like `info` for `a.info`.
+///
+/// This is for trying out other layouts, see `TrashDir::with_info_ext`. A value that is not a
+/// plain extension, like `a/b` or `.info`, is ignored.
+fn info_ext_from_env() -> Option<String> {
+    let ext = env::var(INFO_EXT_ENV_VAR).ok()?;
+    let ext = ext.trim();
+    (!ext.is_empty() && !ext.contains(['/', '.', '\0'])).then(|| ext.to_string())
+}
+
+/// Creates the home trash and its `files` and `info` directories if they are missing.
+///
+/// Directories that already exist keep their permissions.
+pub fn create_home_trash(mode: DirMode) -> Result<()> {
+    let trash = home_trash().context("Neither $XDG_DATA_HOME nor $HOME is set")?;
+    create_trash_dir(&trash, mode)
+}
+
+/// Creates the trash directory `trash` with its `files` and `info` directories.
+fn create_trash_dir(trash: &Path, mode: DirMode) -> Result<()> {
+    if let Some(parent) = trash.parent() {
+        fs::create_dir_all(parent)
+            .with_context(|| format!("Failed to create {}", parent.display()))?;
+    }
+    for dir in [trash.to_path_buf(), trash.join("files"), trash.join("info")] {
+        if dir.is_dir() {
+            continue;
+        }
+        fs::create_dir(&dir)
+            .and_then(|()| set_mode(&dir, mode.0))
+            .with_context(|| format!("Failed to create the trash directory {}", dir.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(unix)]
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    fs::set_permissions(path, fs::Permissions::from_mode(mode))
+}
+
+#[cfg(not(unix))]
+fn set_mode(_: &Path, _: u32) -> io::Result<()> {
+    Ok(())
+}
+
+/// Why an info file is broken when the `files` directory next to it is missing.
+fn missing_files_error() -> anyhow::Error {
+    anyhow::anyhow!("The trashed file is missing, since there is no files directory")
+}
+
+/// Reads and parses the info file at `info_path`.
+///
+/// Fails with `NotARegularFile` when `info_path` is not a regular file, without following it if it
+/// is a symlink.
+pub fn read_info(info_path: &Path) -> Result<TrashInfo> {
+    let file_type =
+        fs::symlink_metadata(info_path).context("Failed to read the info file")?.file_type();
+    if !file_type.is_file() {
+        let file_type = if file_type.is_dir() {
+            "directory"
+        } else if file_type.is_symlink() {
+            "symlink"
+        } else {
+            "special file"
+        };
+        return Err(NotARegularFile { file_type }.into());
+    }
+    Ok(fs::read_to_string(info_path).context("Failed to read the info file")?.parse()?)
+}
+
+/// Replaces the info file at `info_path` with `info`.
+///
+/// The new contents are written next to it first, so the info file is never half written.
+/// Like the trash directories, the file is only accessible by its owner, with the mode `0600`.
+pub fn write_info(info_path: &Path, info: &TrashInfo) -> Result<()> {
+    let mut tmp_path = info_path.as_os_str().to_os_string();
+    tmp_path.push(".tmp");
+    let tmp_path = PathBuf::from(tmp_path);
+    fs::write(&tmp_path, info.to_string())
+        .and_then(|()| set_mode(&tmp_path, 0o600))
+        .and_then(|()| fs::rename(&tmp_path, info_path))
+        .with_context(|| format!("Failed to write the info file {}", info_path.display()))
+}
+
+/// Returns the home trash followed by the trash directories of every mounted drive.
+///
+/// Only trash directories that exist are returned.
+pub fn all_trash_dirs() -> Vec<TrashDir> {
+    let mounts = mount_points();
+    let mut seen = HashSet::new();
+    let mut dirs = Vec::new();
+    if let Some(home) = TrashDir::home() {
+        seen.insert(home.path.clone());
+        dirs.push(home);
+    }
+    let uid = current_uid();
+    for topdir in &mounts {
+        let shared = topdir.join(".Trash");
+        let candidates = [
+            is_valid_shared_trash(&shared).then(|| shared.join(uid.to_string())),
+            Some(topdir.join(format!(".Trash-{uid}"))),
+        ];
+        for path in candidates.into_iter().flatten() {
+            if path.is_dir() && seen.insert(path.clone()) {
+                dirs.push(TrashDir::new(path, topdir.clone()));
+            }
+        }
+    }
+    dirs
+}
+
+/// Why a trash directory was chosen for a path.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrashDirRule {
+    /// The path is on the same filesystem as the home trash.
+    Home,
+    /// The path is on a drive whose topdir has a valid `.Trash` directory shared by all users.
+    SharedTopdir,
+    /// The path is on a drive without a valid shared trash, so it gets its own `.Trash-$uid`.
+    UserTopdir,
+}
+
+impl fmt::Display for TrashDirRule {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrashDirRule::Home => write!(f, "the home trash is on the same filesystem"),
+            TrashDirRule::SharedTopdir => write!(f, "the drive has a shared .Trash directory"),
+            TrashDirRule::UserTopdir => write!(f, "the drive has no valid shared .Trash directory"),
+        }
+    }
+}
+
+/// Returns the trash directory that trashing `path` would use, and why it was chosen.
+///
+/// The trash directory may not exist yet, since it is created when trashing.
+pub fn trash_dir_for(path: &Path) -> Option<(TrashDirRule, PathBuf)> {
+    let path = utils::path::normalize(path);
+    let home_trash = home_trash()?;
+    if same_filesystem(&path, &home_trash) {
+        return Some((TrashDirRule::Home, home_trash));
+    }
+    let topdir = topdir_of(&path, &mount_points());
+    let uid = current_uid();
+    let shared = topdir.join(".Trash");
+    if is_valid_shared_trash(&shared) {
+        Some((TrashDirRule::SharedTopdir, shared.join(uid.to_string())))
+    } else {
+        Some((TrashDirRule::UserTopdir, topdir.join(format!(".Trash-{uid}"))))
+    }
+}
+
+/// Whether `$topdir/.Trash` may hold per-user trash directories.
+///
+/// The spec requires it to be a real directory with the sticky bit set.
+fn is_valid_shared_trash(path: &Path) -> bool {
+    match fs::symlink_metadata(path) {
+        Ok(meta) if meta.is_dir() => is_sticky(&meta),
+        _ => false,
+    }
+}
+
+#[cfg(unix)]
+fn is_sticky(meta: &fs::Metadata) -> bool {
+    use std::os::unix::fs::PermissionsExt;
+
+    meta.permissions().mode() & 0o1000 != 0
+}
+
+#[cfg(not(unix))]
+fn is_sticky(_: &fs::Metadata) -> bool {
+    false
+}
+
+/// Returns the uid of the current user, which names the `.Trash/$uid` and `.Trash-$uid` directories.
+#[cfg(unix)]
+pub fn current_uid() -> u32 {
+    unsafe { libc::getuid() }
+}
+
+/// Returns the uid of the current user.
+///
+/// Platforms other than unix don't have uids or per-drive trash directories, so this is always 0.
+#[cfg(not(unix))]
+pub fn current_uid() -> u32 {
+    0
+}
+
+/// A line of `/proc/mounts`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Mount {
+    path: PathBuf,
+    read_only: bool,
+}
+
+/// Returns the mounts of the system, read from `/proc/mounts` or `/etc/mtab`.
+fn mounts() -> Vec<Mount> {
+    let mounts = fs::read_to_string("/proc/mounts")
+        .or_else(|_| fs::read_to_string("/etc/mtab"))
+        .unwrap_or_default();
+    parse_mounts(&mounts)
+}
+
+fn parse_mounts(s: &str) -> Vec<Mount> {
+    s.lines()
+        .filter_map(|line| {
+            let mut fields = line.split(' ');
+            let path = unescape_mount_path(fields.nth(1)?);
+            let read_only =
+                fields.nth(1).map_or(false, |options| options.split(',').any(|o| o == "ro"));
+            Some(Mount { path, read_only })
+        })
+        .collect()
+}
+
+/// Returns the mount points of the system.
+fn mount_points() -> Vec<PathBuf> {
+    mounts().into_iter().map(|mount| mount.path).collect()
+}
+
+/// Returns the mount point of `path` if that drive is mounted read-only.
+///
+/// Files on it can't be trashed at all, since they can't be moved or removed. This reads the
+/// mount options instead of trying to write, so nothing is created on the drive to find out.
+pub fn read_only_mount(path: &Path) -> Option<PathBuf> {
+    read_only_mount_in(&utils::path::normalize(path), &mounts())
+}
+
+fn read_only_mount_in(path: &Path, mounts: &[Mount]) -> Option<PathBuf> {
+    let points: Vec<_> = mounts.iter().map(|mount| mount.path.clone()).collect();
+    let topdir = topdir_of(path, &points);
+    // a later mount on the same mount point hides the earlier ones
+    let mount = mounts.iter().rev().find(|mount| mount.path == topdir)?;
+    mount.read_only.then_some(topdir)
+}
+
+/// Undoes the octal escapes like `\040` that are used for whitespace in mount paths.
+fn unescape_mount_path(s: &str) -> PathBuf {
+    let mut unescaped = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(i) = rest.find('\\') {
+        unescaped.push_str(&rest[..i]);
+        let escape = rest.get(i + 1..i + 4).and_then(|octal| u8::from_str_radix(octal, 8).ok());
+        match escape {
+            Some(byte) => {
+                unescaped.push(byte as char);
+                rest = &rest[i + 4..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    PathBuf::from(unescaped)
+}
+
+/// Returns the deepest mount point that contains `path`.
+fn topdir_of(path: &Path, mounts: &[PathBuf]) -> PathBuf {
+    mounts
+        .iter()
+        .filter(|mount| path.starts_with(mount))
+        .max_by_key(|mount| mount.components().count())
+        .cloned()
+        .unwrap_or_else(|| PathBuf::from("/"))
+}
+
+/// Returns the home trash directory, `$XDG_DATA_HOME/Trash` or `~/.local/share/Trash`.
+pub fn home_trash() -> Option<PathBuf> {
+    let data_home = env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+        let home = env::var_os("HOME").filter(|dir| !dir.is_empty())?;
+        Some(PathBuf::from(home).join(".local/share"))
+    })?;
+    Some(data_home.join("Trash"))
+}
+
+/// Returns the trash directory that `item` lives in.
+///
+/// This is only available on linux, where the id of an item is the path to its info file.
+pub fn trash_dir_of(item: &TrashItem) -> Option<PathBuf> {
+    if cfg!(target_os = "linux") {
+        Some(Path::new(&item.id).parent()?.parent()?.to_path_buf())
+    } else {
+        None
+    }
+}
+
+/// Whether `item` is in the home trash.
+///
+/// Items are always considered to be in the home trash on platforms with a single trash.
+pub fn is_in_home_trash(item: &TrashItem) -> bool {
+    match (trash_dir_of(item), home_trash()) {
+        (Some(dir), Some(home)) => dir == home,
+        _ => true,
+    }
+}
+
+/// Checks that the `files` and `info` directories of `trash` can be written to.
+///
+/// Directories that don't exist yet are skipped, since they will be created when trashing.
+pub fn check_writable(trash: &Path) -> Result<(), TrashNotWritable> {
+    for dir in [trash.join("files"), trash.join("info")] {
+        if !dir.is_dir() {
+            continue;
+        }
+        if let Err(source) = check_access(&dir) {
+            return Err(TrashNotWritable { dir, source });
+        }
+    }
+    Ok(())
+}
+
+/// Whether items can be trashed into `trash`, creating its directories if needed.
+pub fn is_usable(trash: &Path) -> bool {
+    [trash.join("files"), trash.join("info")].iter().all(|dir| {
+        let ancestor = dir.ancestors().find(|path| path.symlink_metadata().is_ok());
+        ancestor.map_or(false, |ancestor| ancestor.is_dir() && is_writable(ancestor))
+    })
+}
+
+/// Returns the closest existing ancestor of `dir`, or `dir` itself, if it can't be written to.
+///
+/// Missing directories would be created inside that ancestor, so it is the one that has to be
+/// writable.
+pub fn unwritable_ancestor(dir: &Path) -> Option<&Path> {
+    dir.ancestors().find(|path| path.exists()).filter(|path| !is_writable(path))
+}
+
+/// Whether `path` is one of `trash_dirs` or inside one of them.
+///
+/// `path` does not have to exist. Its parent is resolved when it does, so a symlink pointing into
+/// a trash directory does not hide that the path is inside it.
+pub fn is_inside_trash(path: &Path, trash_dirs: &[TrashDir]) -> bool {
+    let resolved = |path: &Path| {
+        let path = utils::path::normalize(path);
+        let parent = path.parent().and_then(|parent| parent.canonicalize().ok());
+        match (parent, path.file_name()) {
+            (Some(parent), Some(name)) => parent.join(name),
+            _ => path,
+        }
+    };
+    let (path, resolved_path) = (utils::path::normalize(path), resolved(path));
+    trash_dirs.iter().any(|trash_dir| {
+        let trash = utils::path::normalize(&trash_dir.path);
+        let resolved_trash = resolved(&trash_dir.path);
+        path.starts_with(&trash) || resolved_path.starts_with(resolved_trash)
+    })
+}
+
+/// Whether `a` and `b` are on the same filesystem.
+///
+/// Paths that don't exist are compared using their closest existing ancestor.
+pub fn same_filesystem(a: &Path, b: &Path) -> bool {
+    match (device_of(a), device_of(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => true,
+    }
+}
+
+#[cfg(unix)]
+fn device_of(path: &Path) -> Option<u64> {
+    use std::os::unix::fs::MetadataExt;
+
+    path.ancestors().find_map(|path| fs::symlink_metadata(path).ok()).map(|meta| meta.dev())
+}
+
+#[cfg(not(unix))]
+fn device_of(_: &Path) -> Option<u64> {
+    None
+}
+
+/// Returns the bytes that unprivileged users can still write to the drive of `path`.
+///
+/// Paths that don't exist use their closest existing ancestor.
+#[cfg(unix)]
+fn available_space(path: &Path) -> Option<u64> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = path.ancestors().find(|path| path.exists())?;
+    let path = CString::new(path.as_os_str().as_bytes()).ok()?;
+    let mut stat = mem::MaybeUninit::<libc::statvfs>::uninit();
+    match unsafe { libc::statvfs(path.as_ptr(), stat.as_mut_ptr()) } {
+        0 => {
+            let stat = unsafe { stat.assume_init() };
+            #[allow(clippy::useless_conversion)]
+            Some(u64::from(stat.f_bavail) * u64::from(stat.f_frsize))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(not(unix))]
+fn available_space(_: &Path) -> Option<u64> {
+    None
+}
+
+fn is_writable(path: &Path) -> bool {
+    check_access(path).is_ok()
+}
+
+/// Checks that `path` can be written to, returning why not otherwise.
+#[cfg(unix)]
+fn check_access(path: &Path) -> io::Result<()> {
+    use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+    let path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    match unsafe { libc::access(path.as_ptr(), libc::W_OK) } {
+        0 => Ok(()),
+        _ => Err(io::Error::last_os_error()),
+    }
+}
+
+#[cfg(not(unix))]
+fn check_access(path: &Path) -> io::Result<()> {
+    if fs::metadata(path)?.permissions().readonly() {
+        return Err(io::Error::new(io::ErrorKind::PermissionDenied, "The path is read-only"));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unescape_mount_path() {
+        assert_eq!(super::unescape_mount_path("/mnt/usb"), Path::new("/mnt/usb"));
+        assert_eq!(super::unescape_mount_path("/mnt/my\\040drive"), Path::new("/mnt/my drive"));
+        assert_eq!(super::unescape_mount_path("/mnt/a\\134b"), Path::new("/mnt/a\\b"));
+        assert_eq!(super::unescape_mount_path("/mnt/a\\b"), Path::new("/mnt/a\\b"));
+    }
+
+    #[test]
+    fn read_only_mount_in() {
+        let mounts = super::parse_mounts(
+            "/dev/sda1 / ext4 rw,relatime 0 0\n\
+             /dev/sdb1 /mnt/usb vfat ro,nosuid,nodev 0 0\n\
+             /dev/sdc1 /mnt/my\\040drive ext4 rw,errors=remount-ro 0 0\n\
+             /dev/sdd1 /mnt/over ext4 ro 0 0\n\
+             /dev/sde1 /mnt/over ext4 rw 0 0\n",
+        );
+        let read_only = |path| super::read_only_mount_in(Path::new(path), &mounts);
+        assert_eq!(read_only("/mnt/usb/a/b"), Some(PathBuf::from("/mnt/usb")));
+        assert_eq!(read_only("/mnt/usb"), Some(PathBuf::from("/mnt/usb")));
+        assert_eq!(read_only("/home/a"), None);
+        assert_eq!(read_only("/mnt/my drive/a"), None);
+        assert_eq!(read_only("/mnt/over/a"), None);
+    }
+
+    #[test]
+    fn topdir_of() {
+        let mounts = [PathBuf::from("/"), PathBuf::from("/mnt"), PathBuf::from("/mnt/usb")];
+        assert_eq!(super::topdir_of(Path::new("/mnt/usb/a"), &mounts), Path::new("/mnt/usb"));
+        assert_eq!(super::topdir_of(Path::new("/mnt/usb2"), &mounts), Path::new("/mnt"));
+        assert_eq!(super::topdir_of(Path::new("/home"), &mounts), Path::new("/"));
+    }
+
+    #[test]
+    fn is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().join("Trash"), PathBuf::from("/"));
+        assert!(trash_dir.is_empty().unwrap());
+        fs::create_dir_all(trash_dir.path.join("info")).unwrap();
+        fs::write(trash_dir.path.join("info/a.trashinfo.tmp"), "").unwrap();
+        assert!(trash_dir.is_empty().unwrap());
+        assert!(all_empty(std::slice::from_ref(&trash_dir)).unwrap());
+        fs::write(trash_dir.path.join("info/a.trashinfo"), "").unwrap();
+        assert!(!trash_dir.is_empty().unwrap());
+        assert!(!all_empty(&[trash_dir]).unwrap());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_since() {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+        fn set_mtime(path: &Path, secs: i64) {
+            let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+            let times = [libc::timeval { tv_sec: secs as _, tv_usec: 0 }; 2];
+            assert_eq!(unsafe { libc::utimes(path.as_ptr(), times.as_ptr()) }, 0);
+        }
+
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        let info = |date: &str| format!("[Trash Info]\nPath=/{date}\nDeletionDate={date}\n");
+        for date in ["2023-06-01T12:00:00", "2023-06-10T12:00:00", "2023-06-20T12:00:00"] {
+            fs::write(dir.path().join(format!("info/{date}.trashinfo")), info(date)).unwrap();
+        }
+        // the mtime is only a hint, the deletion date is checked after parsing
+        set_mtime(&dir.path().join("info/2023-06-01T12:00:00.trashinfo"), 1_700_000_000);
+        // too old to be parsed at all, so it is not reported as broken
+        fs::write(dir.path().join("info/broken.trashinfo"), "").unwrap();
+        set_mtime(&dir.path().join("info/broken.trashinfo"), 0);
+
+        let cutoff = Local
+            .from_local_datetime(
+                &crate::trash_info::parse_trash_datetime("2023-06-05T00:00:00").unwrap(),
+            )
+            .unwrap()
+            .timestamp();
+        let (mut items, broken) = trash_dir.read_since(cutoff);
+        items.sort_by_key(|item| item.time_deleted);
+        let names: Vec<_> = items.iter().map(|item| item.name.as_str()).collect();
+        assert_eq!(names, ["2023-06-10T12:00:00", "2023-06-20T12:00:00"]);
+        assert!(broken.is_empty());
+    }
+
+    #[test]
+    fn rebuild_directorysizes() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        fs::create_dir_all(dir.path().join("files/a dir")).unwrap();
+        fs::write(dir.path().join("files/a dir/file"), "abc").unwrap();
+        fs::write(dir.path().join("files/file"), "abc").unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        fs::write(dir.path().join("info/a dir.trashinfo"), "").unwrap();
+        fs::write(dir.path().join("info/file.trashinfo"), "").unwrap();
+        fs::write(dir.path().join("directorysizes"), "10 1 gone\n20 2 a%20dir\n").unwrap();
+
+        trash_dir.rebuild_directorysizes().unwrap();
+        let size = utils::path::size(&dir.path().join("files/a dir")).unwrap();
+        let mtime = fs::metadata(dir.path().join("info/a dir.trashinfo"))
+            .unwrap()
+            .modified()
+            .unwrap()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_secs();
+        assert_eq!(
+            fs::read_to_string(dir.path().join("directorysizes")).unwrap(),
+            format!("{size} {mtime} a%20dir\n")
+        );
+        let info_path = dir.path().join("info/a dir.trashinfo");
+        let info_mtime = fs::metadata(&info_path).unwrap().modified().unwrap();
+        assert_eq!(cached_directory_size(&info_path, info_mtime), Some(size));
+        assert_eq!(cached_directory_size(&info_path, UNIX_EPOCH), None);
+        assert!(!dir.path().join("directorysizes.tmp").exists());
+    }
+
+    #[test]
+    fn stale_cache_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        assert!(trash_dir.stale_cache_entries().unwrap().is_empty());
+        fs::create_dir(dir.path().join("info")).unwrap();
+        let mut contents = String::from("garbage\n");
+        for name in ["a dir", "b", "c", "d"] {
+            fs::create_dir_all(dir.path().join("files").join(name)).unwrap();
+            fs::write(dir.path().join("files").join(name).join("file"), "abc").unwrap();
+            let info_path = dir.path().join(format!("info/{name}.trashinfo"));
+            fs::write(&info_path, "").unwrap();
+            let size = utils::path::size(&dir.path().join("files").join(name)).unwrap();
+            let mtime = unix_secs(fs::metadata(&info_path).unwrap().modified().unwrap());
+            let mtime = if name == "d" { 1 } else { mtime };
+            let name = encoding::encode_path(Path::new(name));
+            contents.push_str(&format!("{size} {mtime} {name}\n"));
+        }
+        fs::write(dir.path().join("directorysizes"), contents).unwrap();
+        fs::write(dir.path().join("files/a dir/other"), "abc").unwrap();
+        fs::remove_dir_all(dir.path().join("files/b")).unwrap();
+
+        assert_eq!(
+            trash_dir.stale_cache_entries().unwrap(),
+            [
+                dir.path().join("files/a dir"),
+                dir.path().join("files/b"),
+                dir.path().join("files/d")
+            ]
+        );
+        trash_dir.rebuild_directorysizes().unwrap();
+        assert!(trash_dir.stale_cache_entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn import_legacy() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        assert_eq!(trash_dir.import_legacy().unwrap().imported, 0);
+        assert!(!dir.path().join("files").exists());
+
+        let info = "[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\n";
+        for name in ["a", "taken"] {
+            fs::write(dir.path().join(name), name).unwrap();
+            fs::write(dir.path().join(format!("{name}.trashinfo")), info).unwrap();
+        }
+        fs::write(dir.path().join("broken.trashinfo"), "").unwrap();
+        fs::write(dir.path().join("broken"), "").unwrap();
+        fs::write(dir.path().join("no-file.trashinfo"), info).unwrap();
+        fs::create_dir(dir.path().join("loose")).unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        fs::write(dir.path().join("info/taken.trashinfo"), "").unwrap();
+        fs::write(dir.path().join("directorysizes"), "").unwrap();
+
+        let report = trash_dir.import_legacy().unwrap();
+        assert_eq!(report.imported, 2);
+        assert_eq!(
+            report.renamed,
+            [(dir.path().join("taken.trashinfo"), dir.path().join("info/taken.2.trashinfo"))]
+        );
+        assert_eq!(report.orphaned, [dir.path().join("files/loose")]);
+        let failed: Vec<_> = report.failed.iter().map(|entry| entry.info_path.clone()).collect();
+        assert_eq!(
+            failed,
+            [dir.path().join("broken.trashinfo"), dir.path().join("no-file.trashinfo")]
+        );
+        assert_eq!(fs::read_to_string(dir.path().join("files/taken.2")).unwrap(), "taken");
+        assert_eq!(fs::read_to_string(dir.path().join("info/a.trashinfo")).unwrap(), info);
+        assert!(!dir.path().join("a.trashinfo").exists());
+        // a failed entry stays together with its trashed file
+        assert!(dir.path().join("broken").exists());
+        assert!(dir.path().join("directorysizes").exists());
+        assert_eq!(trash_dir.orphans(), [dir.path().join("files/loose")]);
+    }
+
+    #[test]
+    fn count_by_parent() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/mnt"));
+        fs::create_dir(dir.path().join("info")).unwrap();
+        for (name, path) in [("a", "/home/a/Downloads/a"), ("b", "/home/a/Downloads/b"), ("c", "c")]
+        {
+            let info = format!("[Trash Info]\nPath={path}\nDeletionDate=2023-06-01T12:30:45\n");
+            fs::write(dir.path().join(format!("info/{name}.trashinfo")), info).unwrap();
+        }
+        fs::write(dir.path().join("info/broken.trashinfo"), "").unwrap();
+
+        let counts = trash_dir.count_by_parent().unwrap();
+        assert_eq!(
+            counts,
+            HashMap::from([(PathBuf::from("/home/a/Downloads"), 2), (PathBuf::from("/mnt"), 1)])
+        );
+    }
+
+    #[test]
+    fn restore_conflicts() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().join("Trash"), dir.path().to_path_buf());
+        let write_entry = |name: &str| {
+            fs::create_dir_all(trash_dir.path.join("info")).unwrap();
+            fs::write(
+                trash_dir.path.join(format!("info/{name}.trashinfo")),
+                format!("[Trash Info]\nPath={name}\nDeletionDate=2023-06-01T12:30:45\n"),
+            )
+            .unwrap();
+            trash_dir.path.join(format!("info/{name}.trashinfo"))
+        };
+        let files = trash_dir.path.join("files");
+        fs::create_dir_all(files.join("dir/sub")).unwrap();
+        for path in ["dir/a", "dir/b", "dir/sub/c", "dir/sub/d", "file", "free"] {
+            fs::write(files.join(path), "trashed").unwrap();
+        }
+        fs::create_dir_all(dir.path().join("dir/sub")).unwrap();
+        fs::write(dir.path().join("dir/b"), "").unwrap();
+        fs::write(dir.path().join("dir/sub/d"), "").unwrap();
+        fs::write(dir.path().join("file"), "").unwrap();
+
+        let conflicts = trash_dir.restore_conflicts(&write_entry("dir")).unwrap();
+        assert_eq!(conflicts, [dir.path().join("dir/b"), dir.path().join("dir/sub/d")]);
+        let conflicts = trash_dir.restore_conflicts(&write_entry("file")).unwrap();
+        assert_eq!(conflicts, [dir.path().join("file")]);
+        assert!(trash_dir.restore_conflicts(&write_entry("free")).unwrap().is_empty());
+
+        // a file on a directory only conflicts at the top
+        fs::remove_dir_all(dir.path().join("dir")).unwrap();
+        fs::write(dir.path().join("dir"), "").unwrap();
+        let conflicts = trash_dir.restore_conflicts(&write_entry("dir")).unwrap();
+        assert_eq!(conflicts, [dir.path().join("dir")]);
+    }
+
+    #[test]
+    fn verify_readable() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        for name in ["file", "dir", "missing"] {
+            fs::create_dir_all(dir.path().join("info")).unwrap();
+            fs::write(dir.path().join(format!("info/{name}.trashinfo")), "").unwrap();
+        }
+        fs::create_dir_all(dir.path().join("files/dir/sub")).unwrap();
+        fs::write(dir.path().join("files/file"), "abc").unwrap();
+        fs::write(dir.path().join("files/dir/sub/file"), "abc").unwrap();
+
+        for full_read in [false, true] {
+            let unreadable = trash_dir.verify_readable(full_read).unwrap();
+            assert_eq!(unreadable.len(), 1);
+            assert_eq!(unreadable[0].info_path, dir.path().join("info/missing.trashinfo"));
+            assert_eq!(unreadable[0].path, dir.path().join("files/missing"));
+            assert_eq!(unreadable[0].error.kind(), io::ErrorKind::NotFound);
+        }
+    }
+
+    #[test]
+    fn orphans() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        assert!(trash_dir.orphans().is_empty());
+        fs::create_dir_all(dir.path().join("files/orphan")).unwrap();
+        fs::write(dir.path().join("files/item"), "").unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        fs::write(dir.path().join("info/item.trashinfo"), "").unwrap();
+        assert_eq!(trash_dir.orphans(), [dir.path().join("files/orphan")]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_skips_irregular_info_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        let info = "[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\n";
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::create_dir_all(dir.path().join("info/dir.trashinfo")).unwrap();
+        fs::write(dir.path().join("target"), info).unwrap();
+        std::os::unix::fs::symlink(
+            dir.path().join("target"),
+            dir.path().join("info/link.trashinfo"),
+        )
+        .unwrap();
+        fs::write(dir.path().join("info/a.trashinfo"), info).unwrap();
+
+        let (items, mut broken) = trash_dir.read();
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].id, dir.path().join("info/a.trashinfo").into_os_string());
+        broken.sort_by(|a, b| a.info_path.cmp(&b.info_path));
+        let reasons: Vec<_> = broken
+            .iter()
+            .map(|entry| *entry.error.downcast_ref::<NotARegularFile>().unwrap())
+            .collect();
+        assert_eq!(
+            reasons,
+            [NotARegularFile { file_type: "directory" }, NotARegularFile { file_type: "symlink" }]
+        );
+    }
+
+    #[test]
+    fn original_location() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().join(".Trash-1000"), dir.path().to_path_buf());
+        fs::create_dir_all(trash_dir.path.join("info")).unwrap();
+        let relative = trash_dir.path.join("info/a.trashinfo");
+        let info = "[Trash Info]\nPath=dir/a%20b\nDeletionDate=2023-06-01T12:30:45\n";
+        fs::write(&relative, info).unwrap();
+        let absolute = trash_dir.path.join("info/b.trashinfo");
+        let info = "[Trash Info]\nPath=/elsewhere/b\nDeletionDate=2023-06-01T12:30:45\n";
+        fs::write(&absolute, info).unwrap();
+
+        assert_eq!(trash_dir.original_location(&relative).unwrap(), dir.path().join("dir/a b"));
+        assert_eq!(trash_dir.original_location(&absolute).unwrap(), Path::new("/elsewhere/b"));
+        assert!(trash_dir.original_location(&trash_dir.path.join("info/c.trashinfo")).is_err());
+
+        // relative paths can't be resolved once the topdir is gone
+        let unmounted = TrashDir { topdir: dir.path().join("unmounted"), ..trash_dir };
+        let e = unmounted.original_location(&relative).unwrap_err();
+        assert!(e.to_string().contains("which is not mounted"), "{e}");
+        assert_eq!(unmounted.original_location(&absolute).unwrap(), Path::new("/elsewhere/b"));
+    }
+
+    #[test]
+    fn empty_preview() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        assert_eq!(trash_dir.empty_preview().unwrap(), EmptyReport::default());
+        fs::create_dir_all(dir.path().join("files/a dir")).unwrap();
+        fs::write(dir.path().join("files/a dir/file"), "abc").unwrap();
+        fs::write(dir.path().join("files/b"), "abcde").unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        let info = "[Trash Info]\nPath=/x\nDeletionDate=2023-06-01T12:30:45\n";
+        fs::write(dir.path().join("info/a dir.trashinfo"), info).unwrap();
+        fs::write(dir.path().join("info/b.trashinfo"), info).unwrap();
+        fs::write(dir.path().join("info/broken.trashinfo"), "").unwrap();
+
+        let dir_size = utils::path::size(&dir.path().join("files/a dir")).unwrap();
+        let report = trash_dir.empty_preview().unwrap();
+        assert_eq!(report, EmptyReport { items: 2, size: dir_size + 5 });
+
+        // the cached size is used for directories
+        let mtime =
+            fs::metadata(dir.path().join("info/a dir.trashinfo")).unwrap().modified().unwrap();
+        fs::write(
+            dir.path().join("directorysizes"),
+            format!("1000 {} a%20dir\n", unix_secs(mtime)),
+        )
+        .unwrap();
+        assert_eq!(trash_dir.empty_preview().unwrap(), EmptyReport { items: 2, size: 1005 });
+        assert_eq!(trash_dir.info_paths().len(), 3);
+    }
+
+    #[test]
+    fn used_space() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        assert_eq!(trash_dir.used_space().unwrap(), 0);
+        fs::create_dir_all(dir.path().join("files/a dir")).unwrap();
+        fs::write(dir.path().join("files/a dir/file"), "abc").unwrap();
+        fs::write(dir.path().join("files/orphan"), "abcde").unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        let info = "[Trash Info]\nPath=/x\nDeletionDate=2023-06-01T12:30:45\n";
+        fs::write(dir.path().join("info/a dir.trashinfo"), info).unwrap();
+        fs::write(dir.path().join("info/broken.trashinfo"), "").unwrap();
+
+        let dir_size = utils::path::size(&dir.path().join("files/a dir")).unwrap();
+        assert_eq!(trash_dir.used_space().unwrap(), dir_size + 5);
+        // the cached size is used, and the cache itself is not counted
+        let mtime =
+            fs::metadata(dir.path().join("info/a dir.trashinfo")).unwrap().modified().unwrap();
+        fs::write(
+            dir.path().join("directorysizes"),
+            format!("1000 {} a%20dir\n", unix_secs(mtime)),
+        )
+        .unwrap();
+        assert_eq!(trash_dir.used_space().unwrap(), 1005);
+    }
+
+    #[test]
+    fn purge_by_names() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        fs::create_dir_all(dir.path().join("files/a dir")).unwrap();
+        fs::write(dir.path().join("files/a dir/file"), "").unwrap();
+        fs::write(dir.path().join("files/b"), "").unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        for name in ["a dir", "b", "c"] {
+            fs::write(dir.path().join(format!("info/{name}.trashinfo")), "").unwrap();
+        }
+        assert_eq!(trash_dir.find_by_trashed_name("../info/b"), None);
+
+        let report = trash_dir.purge_by_names(&["a dir", "c", "missing", "/b"]).unwrap();
+        assert_eq!(report.purged, 2);
+        assert_eq!(report.not_found, ["missing", "/b"]);
+        assert_eq!(trash_dir.info_paths(), [dir.path().join("info/b.trashinfo")]);
+        assert!(!dir.path().join("files/a dir").exists());
+        assert!(dir.path().join("files/b").exists());
+    }
+
+    #[test]
+    fn health() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        assert_eq!(trash_dir.health(), TrashHealth::Empty);
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::write(dir.path().join("files/a"), "").unwrap();
+        assert_eq!(trash_dir.health(), TrashHealth::MissingInfo);
+        assert_eq!(trash_dir.orphans(), [dir.path().join("files/a")]);
+
+        fs::create_dir(dir.path().join("info")).unwrap();
+        let info = TrashInfo::new(Path::new("/a"), Local::now().naive_local());
+        write_info(&dir.path().join("info/a.trashinfo"), &info).unwrap();
+        assert_eq!(trash_dir.health(), TrashHealth::Healthy);
+        fs::remove_dir_all(dir.path().join("files")).unwrap();
+        assert_eq!(trash_dir.health(), TrashHealth::MissingFiles);
+        let (items, broken) = trash_dir.read();
+        assert!(items.is_empty());
+        assert_eq!(broken.len(), 1);
+        assert!(broken[0].error.to_string().contains("no files directory"), "{}", broken[0].error);
+
+        let claim = trash_dir.claim_name("b").unwrap();
+        assert_eq!(trash_dir.health(), TrashHealth::Healthy);
+        drop(claim);
+    }
+
+    #[test]
+    fn find_by_basename() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        for (name, path) in [("a", "/x/a.txt"), ("a.2", "/y/a.txt"), ("b", "/x/b.txt")] {
+            let info = TrashInfo::new(Path::new(path), Local::now().naive_local());
+            write_info(&dir.path().join(format!("info/{name}.trashinfo")), &info).unwrap();
+        }
+
+        let mut parents: Vec<_> = trash_dir
+            .find_by_basename("a.txt")
+            .into_iter()
+            .map(|item| item.original_parent)
+            .collect();
+        parents.sort();
+        assert_eq!(parents, [Path::new("/x"), Path::new("/y")]);
+        assert_eq!(trash_dir.find_by_basename("b.txt").len(), 1);
+        assert!(trash_dir.find_by_basename("a").is_empty());
+    }
+
+    #[test]
+    fn purge_by_original() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        for (name, path) in [("a", "/x/a"), ("a.2", "/x/a"), ("b", "/x/b")] {
+            fs::write(dir.path().join("files").join(name), "").unwrap();
+            let info = TrashInfo::new(Path::new(path), Local::now().naive_local());
+            write_info(&dir.path().join(format!("info/{name}.trashinfo")), &info).unwrap();
+        }
+
+        let report = trash_dir.purge_by_original(Path::new("/x/./c/../a")).unwrap();
+        assert_eq!(report.purged, 2);
+        assert!(report.not_found.is_empty());
+        assert_eq!(trash_dir.info_paths(), [dir.path().join("info/b.trashinfo")]);
+        assert!(!dir.path().join("files/a").exists());
+
+        let report = trash_dir.purge_by_original(Path::new("/x/a")).unwrap();
+        assert_eq!((report.purged, report.not_found), (0, vec!["/x/a".to_owned()]));
+        assert!(dir.path().join("files/b").exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn put_by_copy() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().join("Trash"), PathBuf::from("/unrelated"));
+        fs::create_dir_all(trash_dir.path.join("files")).unwrap();
+        fs::create_dir_all(trash_dir.path.join("info")).unwrap();
+        let src = dir.path().join("src");
+        fs::create_dir(&src).unwrap();
+        fs::write(src.join("file"), "abc").unwrap();
+        symlink("file", src.join("link")).unwrap();
+        fs::write(trash_dir.path.join("info/src.trashinfo"), "").unwrap();
+
+        let date = crate::trash_info::parse_trash_datetime("2023-06-01T12:30:45").unwrap();
+        let info_path = trash_dir.put_by_copy(&src, date).unwrap();
+        assert_eq!(info_path, trash_dir.path.join("info/src.2.trashinfo"));
+        assert!(!src.exists());
+        let copy = trash_dir.path.join("files/src.2");
+        assert_eq!(fs::read_to_string(copy.join("file")).unwrap(), "abc");
+        assert_eq!(fs::read_link(copy.join("link")).unwrap(), Path::new("file"));
+        let info = read_info(&info_path).unwrap();
+        assert_eq!(trash_dir.original_path(&info), src);
+        assert_eq!(info.deletion_date(), date);
+    }
+
+    #[test]
+    fn check_space() {
+        assert_eq!(super::check_space(10, Some(10)), Ok(()));
+        assert_eq!(super::check_space(10, None), Ok(()));
+        assert_eq!(
+            super::check_space(11, Some(10)),
+            Err(InsufficientSpace { needed: 11, available: 10 })
+        );
+        let dir = tempfile::tempdir().unwrap();
+        assert!(available_space(&dir.path().join("missing/child")).is_some() || cfg!(not(unix)));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn put_by_hardlink() {
+        use std::os::unix::fs::MetadataExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().join("Trash"), PathBuf::from("/"));
+        fs::create_dir_all(trash_dir.path.join("files")).unwrap();
+        fs::create_dir_all(trash_dir.path.join("info")).unwrap();
+        let src = dir.path().join("file");
+        fs::write(&src, "abc").unwrap();
+        let ino = fs::metadata(&src).unwrap().ino();
+
+        let date = crate::trash_info::parse_trash_datetime("2023-06-01T12:30:45").unwrap();
+        let info_path = trash_dir.put_by_hardlink(&src, date).unwrap();
+        assert!(!src.exists());
+        let meta = fs::metadata(trash_dir.path.join("files/file")).unwrap();
+        assert_eq!((meta.ino(), meta.nlink()), (ino, 1));
+        assert_eq!(trash_dir.original_path(&read_info(&info_path).unwrap()), src);
+
+        // the original path is recorded with its symlinked parent resolved
+        let real = dir.path().join("real");
+        fs::create_dir(&real).unwrap();
+        std::os::unix::fs::symlink(&real, dir.path().join("link")).unwrap();
+        fs::write(real.join("linked"), "abc").unwrap();
+        let linked_info = trash_dir.put_by_hardlink(&dir.path().join("link/linked"), date).unwrap();
+        let original_path = trash_dir.original_path(&read_info(&linked_info).unwrap());
+        assert_eq!(original_path, real.canonicalize().unwrap().join("linked"));
+        fs::remove_file(&linked_info).unwrap();
+
+        // nothing is left behind when linking fails
+        assert!(trash_dir.put_by_hardlink(&src, date).is_err());
+        assert_eq!(trash_dir.info_paths(), [info_path]);
+    }
+
+    #[test]
+    fn custom_info_ext() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir =
+            TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/")).with_info_ext("info");
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        // the info files of the default extension are someone else's
+        fs::write(dir.path().join("info/other.trashinfo"), "").unwrap();
+        fs::write(dir.path().join("files/other"), "").unwrap();
+
+        let date = crate::trash_info::parse_trash_datetime("2023-06-01T12:30:45").unwrap();
+        let claim = trash_dir.claim_name("a").unwrap();
+        fs::write(claim.file_path(), "trashed").unwrap();
+        let info_path = claim.commit(&TrashInfo::new(Path::new("/a"), date)).unwrap();
+        assert_eq!(info_path, dir.path().join("info/a.info"));
+        assert_eq!(trash_dir.info_paths(), std::slice::from_ref(&info_path));
+        assert_eq!(trash_dir.read().0.len(), 1);
+        assert_eq!(trash_dir.orphans(), [dir.path().join("files/other")]);
+        assert_eq!(trash_dir.find_by_trashed_name("a"), Some(info_path.clone()));
+
+        let info_path = trash_dir.rename_entry(&info_path, "b").unwrap();
+        assert_eq!(info_path, dir.path().join("info/b.info"));
+        assert_eq!(trash_dir.purge_by_names(&["b"]).unwrap().purged, 1);
+        assert!(trash_dir.is_empty().unwrap());
+        assert!(dir.path().join("info/other.trashinfo").exists());
+    }
+
+    #[test]
+    fn claim_name() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        fs::write(dir.path().join("files/a"), "").unwrap();
+
+        let first = trash_dir.claim_name("a").unwrap();
+        let second = trash_dir.claim_name("a").unwrap();
+        assert_eq!(first.file_path(), dir.path().join("files/a.2"));
+        assert_eq!(second.file_path(), dir.path().join("files/a.3"));
+        drop(second);
+        assert_eq!(trash_dir.info_paths(), [dir.path().join("info/a.2.trashinfo")]);
+
+        // nothing was put at the file path, so the name is given up
+        let date = crate::trash_info::parse_trash_datetime("2023-06-01T12:30:45").unwrap();
+        let info = TrashInfo::new(Path::new("/a"), date);
+        assert!(first.commit(&info).is_err());
+        assert!(trash_dir.info_paths().is_empty());
+
+        let claim = trash_dir.claim_name("a").unwrap();
+        fs::write(claim.file_path(), "trashed").unwrap();
+        let info_path = claim.commit(&info).unwrap();
+        assert_eq!(info_path, dir.path().join("info/a.2.trashinfo"));
+        assert_eq!(read_info(&info_path).unwrap(), info);
+
+        for name in ["", ".", "..", "a/b"] {
+            assert!(trash_dir.claim_name(name).is_err());
+        }
+    }
+
+    #[test]
+    fn rename_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        fs::create_dir(dir.path().join("files")).unwrap();
+        fs::create_dir(dir.path().join("info")).unwrap();
+        for name in ["a", "wanted"] {
+            fs::write(dir.path().join("files").join(name), name).unwrap();
+            fs::write(dir.path().join(format!("info/{name}.trashinfo")), name).unwrap();
+        }
+
+        let info_path =
+            trash_dir.rename_entry(&dir.path().join("info/a.trashinfo"), "wanted").unwrap();
+        assert_eq!(info_path, dir.path().join("info/wanted.2.trashinfo"));
+        assert_eq!(fs::read_to_string(&info_path).unwrap(), "a");
+        assert_eq!(fs::read_to_string(dir.path().join("files/wanted.2")).unwrap(), "a");
+        assert!(!dir.path().join("files/a").exists());
+        assert_eq!(trash_dir.rename_entry(&info_path, "wanted.2").unwrap(), info_path);
+
+        // a name whose info file was just claimed by someone else is skipped
+        fs::write(dir.path().join("info/claimed.trashinfo"), "other").unwrap();
+        let info_path = trash_dir.rename_entry(&info_path, "claimed").unwrap();
+        assert_eq!(info_path, dir.path().join("info/claimed.2.trashinfo"));
+        assert_eq!(fs::read_to_string(dir.path().join("info/claimed.trashinfo")).unwrap(), "other");
+        assert!(!dir.path().join("files/claimed").exists());
+    }
+
+    #[test]
+    fn restore_all_under() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().join("Trash"), PathBuf::from("/"));
+        create_trash_dir(&trash_dir.path, DirMode::default()).unwrap();
+        let recovery = dir.path().join("recovery");
+        for (name, path) in [("a", "/home/a/x"), ("b", "/home/b/../a/y"), ("c", "/taken")] {
+            fs::create_dir(trash_dir.path.join("files").join(name)).unwrap();
+            fs::write(trash_dir.path.join("files").join(name).join("file"), name).unwrap();
+            let info = TrashInfo::new(Path::new(path), Local::now().naive_local());
+            write_info(&trash_dir.info_path_of(OsStr::new(name)), &info).unwrap();
+        }
+        fs::create_dir_all(&recovery).unwrap();
+        fs::write(recovery.join("taken"), "unrelated").unwrap();
+
+        assert!(trash_dir.restore_all_under(&trash_dir.path.join("files")).is_err());
+        let report = trash_dir.restore_all_under(&recovery).unwrap();
+        let mut restored = report.restored.clone();
+        restored.sort();
+        assert_eq!(restored, [recovery.join("home/a/x"), recovery.join("home/a/y")]);
+        assert_eq!(fs::read_to_string(recovery.join("home/a/y/file")).unwrap(), "b");
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(report.failed[0].info_path, trash_dir.info_path_of(OsStr::new("c")));
+        assert_eq!(fs::read_to_string(recovery.join("taken")).unwrap(), "unrelated");
+        assert_eq!(trash_dir.info_paths(), [trash_dir.info_path_of(OsStr::new("c"))]);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    #[ignore = "root can write to any directory, run with --ignored as another user"]
+    fn check_writable() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        assert!(super::check_writable(dir.path()).is_ok());
+        create_trash_dir(dir.path(), DirMode::default()).unwrap();
+        fs::set_permissions(dir.path().join("info"), fs::Permissions::from_mode(0o500)).unwrap();
+
+        let error = anyhow::Error::from(super::check_writable(dir.path()).unwrap_err());
+        let error = error.downcast::<TrashNotWritable>().unwrap();
+        assert_eq!(error.dir, dir.path().join("info"));
+        assert_eq!(error.source.kind(), io::ErrorKind::PermissionDenied);
+        fs::set_permissions(dir.path().join("info"), fs::Permissions::from_mode(0o700)).unwrap();
+    }
+
+    #[test]
+    fn migrate_entry() {
+        let dir = tempfile::tempdir().unwrap();
+        let topdir = dir.path().join("drive");
+        let source = TrashDir::new(topdir.join(".Trash-1000"), topdir.clone());
+        let dest = TrashDir::new(dir.path().join("Trash"), PathBuf::from("/"));
+        for trash in [&source.path, &dest.path] {
+            create_trash_dir(trash, DirMode::default()).unwrap();
+        }
+        fs::create_dir(source.path.join("files/a")).unwrap();
+        fs::write(source.path.join("files/a/file"), "data").unwrap();
+        fs::write(
+            source.path.join("info/a.trashinfo"),
+            "[Trash Info]\nPath=dir/a\nDeletionDate=2023-06-01T12:30:45\nUid=1000\nGid=100\n\
+             DeletedBy=cron\nSha256="
+                .to_owned()
+                + &"a".repeat(64),
+        )
+        .unwrap();
+        fs::write(dest.path.join("info/a.trashinfo"), "taken").unwrap();
+
+        // the info file can't be written, so the trashed file is put back
+        fs::create_dir(dest.path.join("info/a.2.trashinfo.tmp")).unwrap();
+        assert!(source.migrate_entry(&source.path.join("info/a.trashinfo"), &dest).is_err());
+        assert_eq!(fs::read_to_string(source.path.join("files/a/file")).unwrap(), "data");
+        assert!(!dest.path.join("files/a.2").exists());
+        fs::remove_dir(dest.path.join("info/a.2.trashinfo.tmp")).unwrap();
+
+        source.migrate_entry(&source.path.join("info/a.trashinfo"), &dest).unwrap();
+        assert!(source.info_paths().is_empty());
+        assert!(!source.path.join("files/a").exists());
+        assert_eq!(fs::read_to_string(dest.path.join("files/a.2/file")).unwrap(), "data");
+        let info = read_info(&dest.path.join("info/a.2.trashinfo")).unwrap();
+        assert_eq!(dest.original_path(&info), topdir.join("dir/a"));
+        assert_eq!(info.deletion_date().to_string(), "2023-06-01 12:30:45");
+        assert_eq!((info.uid(), info.gid()), (Some(1000), Some(100)));
+        assert_eq!(info.deleted_by(), Some("cron"));
+        assert_eq!(info.sha256(), Some("a".repeat(64).as_str()));
+    }
+}
@@ -0,0 +1,129 @@
+//! Reading the items of trash directories one info file at a time.
+
+use std::{fs, vec};
+
+use trash::TrashItem;
+
+use super::{BrokenEntry, TrashDir, TrashHealth};
+
+/// The items of a single trash directory, read lazily from `TrashDir::entries`.
+///
+/// An info file that can't be read is yielded as a `BrokenEntry` and the next one is read after
+/// it. A trash directory whose `info` directory can't be read yields nothing.
+#[derive(Debug)]
+pub struct Entries {
+    trash_dir: TrashDir,
+    read_dir: Option<fs::ReadDir>,
+    files_missing: bool,
+}
+
+impl Iterator for Entries {
+    type Item = Result<TrashItem, BrokenEntry>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            let info_path = match self.read_dir.as_mut()?.next()? {
+                Ok(entry) => entry.path(),
+                Err(_) => continue,
+            };
+            if !self.trash_dir.is_info_path(&info_path) {
+                continue;
+            }
+            if self.files_missing {
+                return Some(Err(BrokenEntry { info_path, error: super::missing_files_error() }));
+            }
+            return Some(
+                self.trash_dir
+                    .read_item(&info_path)
+                    .map_err(|error| BrokenEntry { info_path, error }),
+            );
+        }
+    }
+}
+
+impl TrashDir {
+    /// Reads the items in this trash directory lazily, in the order the info files are listed in.
+    ///
+    /// Like `read`, this yields every info file as broken when the `files` directory is missing.
+    pub fn entries(&self) -> Entries {
+        Entries {
+            trash_dir: self.clone(),
+            read_dir: fs::read_dir(self.path.join("info")).ok(),
+            files_missing: self.health() == TrashHealth::MissingFiles,
+        }
+    }
+}
+
+/// The items of several trash directories, one trash directory after the other, each tagged with
+/// the trash directory it is in.
+///
+/// Nothing is read before it is needed, so finding the first match stops reading there. A trash
+/// directory that can't be read or has broken info files does not stop the others.
+#[derive(Debug)]
+pub struct MergedTrashIter {
+    trash_dirs: vec::IntoIter<TrashDir>,
+    current: Option<(TrashDir, Entries)>,
+}
+
+impl MergedTrashIter {
+    pub fn new(trash_dirs: impl IntoIterator<Item = TrashDir>) -> MergedTrashIter {
+        let trash_dirs: Vec<_> = trash_dirs.into_iter().collect();
+        MergedTrashIter { trash_dirs: trash_dirs.into_iter(), current: None }
+    }
+}
+
+impl Iterator for MergedTrashIter {
+    type Item = (TrashDir, Result<TrashItem, BrokenEntry>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((trash_dir, entries)) = &mut self.current {
+                if let Some(entry) = entries.next() {
+                    return Some((trash_dir.clone(), entry));
+                }
+            }
+            let trash_dir = self.trash_dirs.next()?;
+            let entries = trash_dir.entries();
+            self.current = Some((trash_dir, entries));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::{Path, PathBuf};
+
+    use super::*;
+
+    #[test]
+    fn merged_trash_iter() {
+        let dir = tempfile::tempdir().unwrap();
+        let info = "[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\n";
+        let trash_dirs: Vec<_> = ["first", "unreadable", "second"]
+            .iter()
+            .map(|name| TrashDir::new(dir.path().join(name), PathBuf::from("/")))
+            .collect();
+        for name in ["first", "second"] {
+            fs::create_dir_all(dir.path().join(name).join("files")).unwrap();
+            fs::create_dir_all(dir.path().join(name).join("info")).unwrap();
+            fs::write(dir.path().join(name).join("info/a.trashinfo"), info).unwrap();
+        }
+        fs::write(dir.path().join("first/info/broken.trashinfo"), "").unwrap();
+
+        let entries: Vec<_> = MergedTrashIter::new(trash_dirs.clone())
+            .map(|(trash_dir, entry)| (trash_dir.path, entry.is_ok()))
+            .collect();
+        let first = dir.path().join("first");
+        let second = dir.path().join("second");
+        assert_eq!(entries.len(), 3);
+        assert_eq!(entries.iter().filter(|(path, _)| *path == first).count(), 2);
+        assert_eq!(entries.iter().filter(|(_, ok)| !ok).count(), 1);
+        assert_eq!(entries.last(), Some(&(second, true)));
+
+        let (trash_dir, item) = MergedTrashIter::new(trash_dirs)
+            .find_map(|(trash_dir, entry)| Some((trash_dir, entry.ok()?)))
+            .unwrap();
+        assert_eq!(trash_dir.path, first);
+        assert_eq!(item.original_path(), Path::new("/a"));
+    }
+}
@@ -0,0 +1,165 @@
+//! Watching a trash directory for entries that are added or removed.
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    ffi::OsString,
+    fs, thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+
+use super::TrashDir;
+
+/// How often `TrashDir::watch` reads the info directory.
+pub const POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How many polls `TrashDir::watch` waits at most for the info directory to stop changing.
+pub const MAX_DEBOUNCE_POLLS: usize = 10;
+
+/// A change to the entries of a trash directory.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TrashEvent {
+    /// An entry with this name in the files directory was added
+    Added(OsString),
+    /// The entry with this name in the files directory was removed
+    Removed(OsString),
+}
+
+impl TrashDir {
+    /// Watches this trash directory for entries that are added or removed.
+    ///
+    /// The info directory is polled every `POLL_INTERVAL`. Changes are only reported once it
+    /// stayed the same for one more poll, so a burst of changes like emptying the trash is
+    /// reported together, and entries that only existed in between are left out. While it keeps
+    /// changing, like during a long 'trashy put', what changed so far is reported after
+    /// `MAX_DEBOUNCE_POLLS` polls. The iterator never ends.
+    pub fn watch(&self) -> Result<impl Iterator<Item = TrashEvent>> {
+        self.watch_every(POLL_INTERVAL)
+    }
+
+    fn watch_every(&self, interval: Duration) -> Result<Watch> {
+        fs::metadata(&self.path)
+            .with_context(|| format!("Failed to watch {}", self.path.display()))?;
+        Ok(Watch {
+            names: trashed_names(self),
+            trash_dir: self.clone(),
+            interval,
+            pending: VecDeque::new(),
+        })
+    }
+}
+
+struct Watch {
+    trash_dir: TrashDir,
+    interval: Duration,
+    /// The names that were reported last
+    names: BTreeSet<OsString>,
+    pending: VecDeque<TrashEvent>,
+}
+
+impl Watch {
+    /// Waits until the info directory stops changing, or for `MAX_DEBOUNCE_POLLS` polls, and
+    /// queues what changed since the last poll.
+    fn poll(&mut self) {
+        let mut names = trashed_names(&self.trash_dir);
+        for _ in 0..MAX_DEBOUNCE_POLLS {
+            thread::sleep(self.interval);
+            let next = trashed_names(&self.trash_dir);
+            if next == names {
+                break;
+            }
+            names = next;
+        }
+        let removed = self.names.difference(&names).cloned().map(TrashEvent::Removed);
+        let added = names.difference(&self.names).cloned().map(TrashEvent::Added);
+        self.pending.extend(removed.chain(added));
+        self.names = names;
+    }
+}
+
+impl Iterator for Watch {
+    type Item = TrashEvent;
+
+    fn next(&mut self) -> Option<TrashEvent> {
+        loop {
+            if let Some(event) = self.pending.pop_front() {
+                return Some(event);
+            }
+            self.poll();
+        }
+    }
+}
+
+fn trashed_names(trash_dir: &TrashDir) -> BTreeSet<OsString> {
+    trash_dir
+        .info_paths()
+        .into_iter()
+        .filter_map(|info_path| info_path.file_stem().map(|name| name.to_os_string()))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    #[test]
+    fn watch() {
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        let info_dir = dir.path().join("info");
+        fs::create_dir(&info_dir).unwrap();
+        fs::write(info_dir.join("old.trashinfo"), "").unwrap();
+        let mut watch = trash_dir.watch_every(Duration::from_millis(10)).unwrap();
+
+        let writer = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            fs::write(info_dir.join("a.trashinfo"), "").unwrap();
+            fs::write(info_dir.join("b.trashinfo"), "").unwrap();
+            fs::remove_file(info_dir.join("old.trashinfo")).unwrap();
+        });
+        let events: Vec<_> = watch.by_ref().take(3).collect();
+        writer.join().unwrap();
+        assert_eq!(
+            events,
+            [
+                TrashEvent::Removed("old".into()),
+                TrashEvent::Added("a".into()),
+                TrashEvent::Added("b".into())
+            ]
+        );
+        assert!(TrashDir::new(dir.path().join("missing"), PathBuf::from("/")).watch().is_err());
+    }
+
+    #[test]
+    fn watch_while_changing() {
+        use std::sync::{
+            atomic::{AtomicBool, Ordering},
+            Arc,
+        };
+
+        let dir = tempfile::tempdir().unwrap();
+        let trash_dir = TrashDir::new(dir.path().to_path_buf(), PathBuf::from("/"));
+        let info_dir = dir.path().join("info");
+        fs::create_dir(&info_dir).unwrap();
+        let mut watch = trash_dir.watch_every(Duration::from_millis(10)).unwrap();
+
+        let done = Arc::new(AtomicBool::new(false));
+        let writer = thread::spawn({
+            let done = done.clone();
+            move || {
+                for i in 0..200 {
+                    fs::write(info_dir.join(format!("{i}.trashinfo")), "").unwrap();
+                    thread::sleep(Duration::from_millis(5));
+                }
+                done.store(true, Ordering::SeqCst);
+            }
+        });
+        let event = watch.next().unwrap();
+        assert!(!done.load(Ordering::SeqCst), "{event:?} was only reported after the writes");
+        assert!(matches!(event, TrashEvent::Added(_)));
+        writer.join().unwrap();
+    }
+}
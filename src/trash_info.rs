@@ -0,0 +1,600 @@
+use std::{
+    env, error, fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use chrono::{NaiveDateTime, Timelike};
+use percent_encoding::{AsciiSet, CONTROLS};
+
+use crate::{
+    encoding::{self, DecodeError, EncodePolicy},
+    path,
+};
+
+/// The first line of every `.trashinfo` file.
+pub const TRASH_INFO_HEADER: &str = "[Trash Info]";
+/// The key of the percent-encoded original path.
+pub const TRASH_INFO_PATH_KEY: &str = "Path";
+/// The key of the date the file was trashed.
+pub const TRASH_INFO_DATE_KEY: &str = "DeletionDate";
+/// The key of the user id that owned the file, which only some tools write.
+pub const TRASH_INFO_UID_KEY: &str = "Uid";
+/// The key of the group id that owned the file, which only some tools write.
+pub const TRASH_INFO_GID_KEY: &str = "Gid";
+/// The key of a label telling who or what trashed the file, which only trashy writes.
+pub const TRASH_INFO_DELETED_BY_KEY: &str = "DeletedBy";
+/// The key of the SHA-256 checksum of the contents of a regular file, which only trashy writes.
+pub const TRASH_INFO_SHA256_KEY: &str = "Sha256";
+/// The key of the namespace an application put the file into, which only trashy writes.
+pub const TRASH_INFO_NAMESPACE_KEY: &str = "Namespace";
+
+/// The characters that are percent-encoded in `DeletedBy` and `Namespace` values, so that a
+/// label stays on one line and reads back as it was written.
+const LABEL_ENCODE_SET: &AsciiSet = &CONTROLS.add(b'%');
+
+/// The extension of info files.
+pub const TRASH_INFO_EXT: &str = "trashinfo";
+
+/// The format of `DeletionDate` values.
+pub const DATE_FORMAT: &str = "%Y-%m-%dT%H:%M:%S";
+
+/// The contents of a `.trashinfo` file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrashInfo {
+    path: String,
+    deletion_date: NaiveDateTime,
+    uid: Option<u32>,
+    gid: Option<u32>,
+    deleted_by: Option<String>,
+    sha256: Option<String>,
+    namespace: Option<String>,
+}
+
+impl TrashInfo {
+    /// Creates the info of a file that was trashed from `path` at `deletion_date`.
+    ///
+    /// `DeletionDate` values only have a precision of seconds, so the fraction of a second is
+    /// dropped here already. This way the info equals the one that is read back after writing it.
+    pub fn new(path: &Path, deletion_date: NaiveDateTime) -> TrashInfo {
+        TrashInfo {
+            path: encoding::encode_path(path),
+            deletion_date: whole_seconds(deletion_date),
+            uid: None,
+            gid: None,
+            deleted_by: None,
+            sha256: None,
+            namespace: None,
+        }
+    }
+
+    /// The original path as written in the file, which is percent-encoded.
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    /// Whether the original path is relative to the topdir of the trash, which only the trash
+    /// directories of mounted drives write.
+    ///
+    /// Such an entry can only be put back while its drive is mounted, so a UI can mark it.
+    pub fn is_relative_original(&self) -> bool {
+        self.path_decoded().is_relative()
+    }
+
+    /// The original path, which may be relative to the topdir of the trash.
+    ///
+    /// A relative path is returned as it is, without resolving it against any topdir.
+    pub fn path_decoded(&self) -> PathBuf {
+        encoding::decode_path(&self.path).unwrap_or_else(|_| {
+            // only fails where paths must be unicode, so fall back to replacing invalid bytes
+            PathBuf::from(
+                percent_encoding::percent_decode_str(&self.path).decode_utf8_lossy().into_owned(),
+            )
+        })
+    }
+
+    /// The original path with a leading `$HOME` replaced by `~`, like `~/Downloads/a.txt`.
+    ///
+    /// Paths outside of the home directory, and every path when `$HOME` is not set, are
+    /// returned as they are.
+    pub fn original_path_tilde(&self) -> Result<String, DecodeError> {
+        let path = encoding::decode_path(&self.path)?;
+        Ok(tilde_path(&path, env::var_os("HOME").as_deref().map(Path::new)))
+    }
+
+    /// Whether `other` has the same original path, no matter when either was trashed.
+    ///
+    /// The decoded paths are compared after lexically removing `.`, `..`, and trailing slashes.
+    pub fn equals_ignoring_date(&self, other: &TrashInfo) -> bool {
+        path::normalize(&self.path_decoded()) == path::normalize(&other.path_decoded())
+    }
+
+    /// The lowercased extension of the original path, like `gz` for `foo.tar.gz`.
+    ///
+    /// This is useful to group items by file type. Names without an extension return `None`.
+    pub fn original_extension(&self) -> Option<String> {
+        Some(self.path_decoded().extension()?.to_string_lossy().to_lowercase())
+    }
+
+    /// When the file was trashed, in local time, in whole seconds.
+    pub fn deletion_date(&self) -> NaiveDateTime {
+        self.deletion_date
+    }
+
+    /// The user id recorded by `Uid=`, if any.
+    pub fn uid(&self) -> Option<u32> {
+        self.uid
+    }
+
+    /// The group id recorded by `Gid=`, if any.
+    pub fn gid(&self) -> Option<u32> {
+        self.gid
+    }
+
+    /// The label recorded by `DeletedBy=`, telling who or what trashed the file, if any.
+    pub fn deleted_by(&self) -> Option<&str> {
+        self.deleted_by.as_deref()
+    }
+
+    /// The checksum recorded by `Sha256=` as lowercase hex digits, if any.
+    pub fn sha256(&self) -> Option<&str> {
+        self.sha256.as_deref()
+    }
+
+    /// The namespace recorded by `Namespace=`, telling which application put the file, if any.
+    pub fn namespace(&self) -> Option<&str> {
+        self.namespace.as_deref()
+    }
+
+    /// The same info with `deletion_date` instead, keeping the original path as it is written.
+    ///
+    /// Like in `new`, the fraction of a second is dropped.
+    pub fn with_deletion_date(self, deletion_date: NaiveDateTime) -> TrashInfo {
+        TrashInfo { deletion_date: whole_seconds(deletion_date), ..self }
+    }
+
+    /// The same info with `path` as the original path instead, keeping the deletion date and
+    /// every other key.
+    pub fn with_path(self, path: &Path) -> TrashInfo {
+        TrashInfo { path: encoding::encode_path(path), ..self }
+    }
+
+    /// The same info with `label` recorded as who or what trashed the file.
+    pub fn with_deleted_by(self, label: impl Into<String>) -> TrashInfo {
+        TrashInfo { deleted_by: Some(label.into()), ..self }
+    }
+
+    /// The same info with `checksum` recorded as the SHA-256 of the contents of the file.
+    ///
+    /// `checksum` is stored in lowercase, like it is compared after restoring.
+    pub fn with_sha256(self, checksum: &str) -> TrashInfo {
+        TrashInfo { sha256: Some(checksum.to_ascii_lowercase()), ..self }
+    }
+
+    /// The same info with the file recorded as put into `namespace`.
+    ///
+    /// Namespaced entries are stored next to all others, so their trashed names are unique
+    /// across the whole trash directory and not only within the namespace.
+    pub fn with_namespace(self, namespace: impl Into<String>) -> TrashInfo {
+        TrashInfo { namespace: Some(namespace.into()), ..self }
+    }
+
+    /// The same info with its original path encoded by `policy` instead of the `Standard`
+    /// policy.
+    pub fn with_encode_policy(self, policy: EncodePolicy) -> TrashInfo {
+        TrashInfo { path: encoding::encode_path_with(&self.path_decoded(), policy), ..self }
+    }
+}
+
+/// Drops the fraction of a second from `date`, which `DeletionDate` values can't hold.
+fn whole_seconds(date: NaiveDateTime) -> NaiveDateTime {
+    date.with_nanosecond(0).unwrap_or(date)
+}
+
+/// Displays `path` with a leading `home` replaced by `~`.
+///
+/// An empty `home` or `/` is ignored, since every absolute path would start with it.
+pub fn tilde_path(path: &Path, home: Option<&Path>) -> String {
+    let home = home.filter(|home| home.is_absolute() && home.parent().is_some());
+    match home.and_then(|home| path.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".into(),
+        Some(rest) => format!("~/{}", rest.to_string_lossy()),
+        None => path.to_string_lossy().into_owned(),
+    }
+}
+
+/// Formats of `DeletionDate` values written by other trash tools, which are accepted when reading.
+const LENIENT_DATE_FORMATS: &[&str] = &["%Y-%m-%d %H:%M:%S"];
+
+/// Parses a `DeletionDate` value.
+pub fn parse_trash_datetime(s: &str) -> Result<NaiveDateTime, ParseError> {
+    std::iter::once(DATE_FORMAT)
+        .chain(LENIENT_DATE_FORMATS.iter().copied())
+        .find_map(|format| NaiveDateTime::parse_from_str(s, format).ok())
+        .ok_or_else(|| ParseError::InvalidDeletionDate(s.to_string()))
+}
+
+/// Why the contents of a `.trashinfo` file could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseError {
+    MissingHeader,
+    InvalidLine(String),
+    MissingPath,
+    MissingDeletionDate,
+    InvalidDeletionDate(String),
+}
+
+impl fmt::Display for TrashInfo {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{TRASH_INFO_HEADER}")?;
+        // paths read from other tools may contain control characters like a lone `\r`
+        let path = percent_encoding::utf8_percent_encode(&self.path, CONTROLS);
+        writeln!(f, "{TRASH_INFO_PATH_KEY}={path}")?;
+        writeln!(f, "{TRASH_INFO_DATE_KEY}={}", self.deletion_date.format(DATE_FORMAT))?;
+        if let Some(uid) = self.uid {
+            writeln!(f, "{TRASH_INFO_UID_KEY}={uid}")?;
+        }
+        if let Some(gid) = self.gid {
+            writeln!(f, "{TRASH_INFO_GID_KEY}={gid}")?;
+        }
+        if let Some(label) = &self.deleted_by {
+            let label = percent_encoding::utf8_percent_encode(label, LABEL_ENCODE_SET);
+            writeln!(f, "{TRASH_INFO_DELETED_BY_KEY}={label}")?;
+        }
+        if let Some(checksum) = &self.sha256 {
+            writeln!(f, "{TRASH_INFO_SHA256_KEY}={checksum}")?;
+        }
+        if let Some(namespace) = &self.namespace {
+            let namespace = percent_encoding::utf8_percent_encode(namespace, LABEL_ENCODE_SET);
+            writeln!(f, "{TRASH_INFO_NAMESPACE_KEY}={namespace}")?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingHeader => {
+                write!(f, "The file does not start with {TRASH_INFO_HEADER}")
+            }
+            ParseError::InvalidLine(line) => write!(f, "The line `{line}` is not a key-value pair"),
+            ParseError::MissingPath => write!(f, "The {TRASH_INFO_PATH_KEY} key is missing"),
+            ParseError::MissingDeletionDate => {
+                write!(f, "The {TRASH_INFO_DATE_KEY} key is missing")
+            }
+            ParseError::InvalidDeletionDate(date) => {
+                write!(f, "The {TRASH_INFO_DATE_KEY} `{date}` is not a valid date")
+            }
+        }
+    }
+}
+
+impl error::Error for ParseError {}
+
+impl FromStr for TrashInfo {
+    type Err = ParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        parse_trash_info(s)
+    }
+}
+
+/// Parses the contents of a `.trashinfo` file.
+///
+/// This is the same as `s.parse::<TrashInfo>()`.
+/// Only `Path` and `DeletionDate` are required. Every other key, like a `Version` written by a
+/// newer format, is ignored, so files stay readable as the format evolves.
+/// `Uid` and `Gid` are kept when they are valid ids and ignored otherwise, and so is `Sha256`
+/// when it is 64 hex digits. `DeletedBy` and `Namespace` are optional too.
+/// A byte order mark and blank lines before the header, which some tools write, are skipped.
+pub fn parse_trash_info(s: &str) -> Result<TrashInfo, ParseError> {
+    let s = s.strip_prefix('\u{feff}').unwrap_or(s);
+    let mut lines = s.lines().map(str::trim_end).skip_while(|line| line.is_empty());
+    if lines.next() != Some(TRASH_INFO_HEADER) {
+        return Err(ParseError::MissingHeader);
+    }
+    let mut path = None;
+    let mut deletion_date = None;
+    let mut uid = None;
+    let mut gid = None;
+    let mut deleted_by = None;
+    let mut sha256 = None;
+    let mut namespace = None;
+    for line in lines {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        // keys of any following group don't belong to the trash info
+        if line.starts_with('[') {
+            break;
+        }
+        let (key, value) =
+            line.split_once('=').ok_or_else(|| ParseError::InvalidLine(line.to_string()))?;
+        match key.trim() {
+            TRASH_INFO_PATH_KEY if path.is_none() => path = Some(value.trim().to_string()),
+            TRASH_INFO_DATE_KEY if deletion_date.is_none() => {
+                deletion_date = Some(parse_trash_datetime(value.trim())?);
+            }
+            TRASH_INFO_UID_KEY if uid.is_none() => uid = value.trim().parse().ok(),
+            TRASH_INFO_GID_KEY if gid.is_none() => gid = value.trim().parse().ok(),
+            TRASH_INFO_DELETED_BY_KEY if deleted_by.is_none() => {
+                let label = percent_encoding::percent_decode_str(value.trim()).decode_utf8_lossy();
+                deleted_by = Some(label.into_owned());
+            }
+            TRASH_INFO_SHA256_KEY if sha256.is_none() => {
+                let checksum = value.trim();
+                sha256 = (checksum.len() == 64 && checksum.bytes().all(|b| b.is_ascii_hexdigit()))
+                    .then(|| checksum.to_ascii_lowercase());
+            }
+            TRASH_INFO_NAMESPACE_KEY if namespace.is_none() => {
+                let value = percent_encoding::percent_decode_str(value.trim()).decode_utf8_lossy();
+                namespace = Some(value.into_owned());
+            }
+            _ => (),
+        }
+    }
+    Ok(TrashInfo {
+        path: path.ok_or(ParseError::MissingPath)?,
+        deletion_date: deletion_date.ok_or(ParseError::MissingDeletionDate)?,
+        uid,
+        gid,
+        deleted_by,
+        sha256,
+        namespace,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(s: &str) -> Result<TrashInfo, ParseError> {
+        s.parse()
+    }
+
+    #[test]
+    fn parse_succeed() {
+        let info = parse(
+            "[Trash Info]\nPath=/home/user/some%20file.txt\nDeletionDate=2023-06-01T12:30:45\n",
+        )
+        .unwrap();
+        assert_eq!(info.path(), "/home/user/some%20file.txt");
+        assert_eq!(info.path_decoded(), Path::new("/home/user/some file.txt"));
+        assert_eq!(info.deletion_date().to_string(), "2023-06-01 12:30:45");
+    }
+
+    #[test]
+    fn parse_ignores_version() {
+        let info = parse(
+            "[Trash Info]\nVersion=2\nPath=/a\nDeletionDate=2023-06-01T12:30:45\nChecksum=abc\n",
+        )
+        .unwrap();
+        assert_eq!(info.to_string(), "[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\n");
+    }
+
+    #[test]
+    fn parse_space_separated_date() {
+        let s = "[Trash Info]\nPath=/a\nDeletionDate=2023-06-01 12:30:45\n";
+        let info = parse(s).unwrap();
+        assert_eq!(info.deletion_date().to_string(), "2023-06-01 12:30:45");
+        assert_eq!(info.to_string(), "[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\n");
+    }
+
+    #[test]
+    fn equals_ignoring_date() {
+        let info =
+            |path, date| parse(&format!("[Trash Info]\nPath={path}\nDeletionDate={date}")).unwrap();
+        let a = info("/a/b", "2023-06-01T12:30:45");
+        assert!(a.equals_ignoring_date(&info("/a/./c/../b/", "2020-01-01T00:00:00")));
+        assert!(a.equals_ignoring_date(&info("/a/%62", "2023-06-01T12:30:45")));
+        assert!(!a.equals_ignoring_date(&info("/a/c", "2023-06-01T12:30:45")));
+        assert_ne!(a, info("/a/b", "2020-01-01T00:00:00"));
+    }
+
+    #[test]
+    fn original_extension() {
+        let extension = |path| {
+            parse(&format!("[Trash Info]\nPath={path}\nDeletionDate=2023-06-01T12:30:45"))
+                .unwrap()
+                .original_extension()
+        };
+        assert_eq!(extension("/a/foo.tar.gz"), Some("gz".into()));
+        assert_eq!(extension("/a/Photo.JPG"), Some("jpg".into()));
+        assert_eq!(extension("/a/b%2Ec"), Some("c".into()));
+        assert_eq!(extension("/a/Makefile"), None);
+        assert_eq!(extension("/a/.bashrc"), None);
+    }
+
+    #[test]
+    fn tilde_path() {
+        let home = Some(Path::new("/home/alice"));
+        let tilde = |path| super::tilde_path(Path::new(path), home);
+        assert_eq!(tilde("/home/alice/Downloads/a.txt"), "~/Downloads/a.txt");
+        assert_eq!(tilde("/home/alice"), "~");
+        assert_eq!(tilde("/home/alicia/a"), "/home/alicia/a");
+        assert_eq!(tilde("/tmp/a"), "/tmp/a");
+        assert_eq!(super::tilde_path(Path::new("/tmp/a"), None), "/tmp/a");
+        assert_eq!(super::tilde_path(Path::new("/tmp/a"), Some(Path::new("/"))), "/tmp/a");
+
+        let info =
+            parse("[Trash Info]\nPath=/tmp/a%20b\nDeletionDate=2023-06-01T12:30:45\n").unwrap();
+        assert_eq!(info.original_path_tilde().unwrap(), "/tmp/a b");
+    }
+
+    #[test]
+    fn with_deletion_date() {
+        let info = parse("[Trash Info]\nPath=/a%20b\nDeletionDate=2023-06-01T12:30:45\n").unwrap();
+        let date = parse_trash_datetime("2020-01-02T03:04:05").unwrap();
+        let changed = info.clone().with_deletion_date(date);
+        assert_eq!(changed.deletion_date(), date);
+        assert_eq!(changed.path(), "/a%20b");
+        assert!(changed.equals_ignoring_date(&info));
+    }
+
+    #[test]
+    fn with_path() {
+        let s = "[Trash Info]\nPath=dir/a\nDeletionDate=2023-06-01T12:30:45\nUid=1000\nGid=100\n";
+        let info = parse(s).unwrap().with_deleted_by("cron").with_sha256(&"a".repeat(64));
+        let changed = info.clone().with_path(Path::new("/mnt/dir/a b"));
+        assert_eq!(changed.path(), "/mnt/dir/a%20b");
+        assert_eq!(changed.deletion_date(), info.deletion_date());
+        assert_eq!(changed.with_path(Path::new("dir/a")), info);
+    }
+
+    #[test]
+    fn owner() {
+        let s = "[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\nUid=1000\nGid=100\n";
+        let info = parse(s).unwrap();
+        assert_eq!((info.uid(), info.gid()), (Some(1000), Some(100)));
+        assert_eq!(info.to_string(), s);
+        assert!(info.equals_ignoring_date(&parse(s).unwrap()));
+
+        let info =
+            parse("[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\nUid=-1\nGid=").unwrap();
+        assert_eq!((info.uid(), info.gid()), (None, None));
+        assert_eq!(info.to_string(), "[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\n");
+    }
+
+    #[test]
+    fn deleted_by() {
+        let info = parse("[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\n").unwrap();
+        assert_eq!(info.deleted_by(), None);
+
+        let labeled = info.with_deleted_by("cron: cleanup\n100%");
+        assert_eq!(
+            labeled.to_string(),
+            "[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\nDeletedBy=cron: cleanup%0A100%25\n"
+        );
+        let parsed = parse(&labeled.to_string()).unwrap();
+        assert_eq!(parsed.deleted_by(), Some("cron: cleanup\n100%"));
+        assert_eq!(parsed, labeled);
+    }
+
+    #[test]
+    fn namespace() {
+        let info = parse("[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\n").unwrap();
+        assert_eq!(info.namespace(), None);
+
+        let namespaced = info.with_deleted_by("editor").with_namespace("my editor%");
+        assert_eq!(
+            namespaced.to_string(),
+            "[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\nDeletedBy=editor\nNamespace=my editor%25\n"
+        );
+        let parsed = parse(&namespaced.to_string()).unwrap();
+        assert_eq!(parsed.namespace(), Some("my editor%"));
+        assert_eq!(parsed, namespaced);
+    }
+
+    #[test]
+    fn sha256() {
+        let info = parse("[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\n").unwrap();
+        assert_eq!(info.sha256(), None);
+        let checksum = "AB".repeat(32);
+        let info = info.with_sha256(&checksum);
+        assert_eq!(info.sha256(), Some("ab".repeat(32).as_str()));
+        assert_eq!(parse(&info.to_string()), Ok(info.clone()));
+        let invalid = "[Trash Info]\nPath=/a\nDeletionDate=2023-06-01T12:30:45\nSha256=abc\n";
+        assert_eq!(parse(invalid).unwrap().sha256(), None);
+    }
+
+    #[test]
+    fn is_relative_original() {
+        let info =
+            parse("[Trash Info]\nPath=dir/a%20b\nDeletionDate=2023-06-01T12:30:45\n").unwrap();
+        assert!(info.is_relative_original());
+        assert_eq!(info.path_decoded(), Path::new("dir/a b"));
+        let info = parse("[Trash Info]\nPath=/dir/a\nDeletionDate=2023-06-01T12:30:45\n").unwrap();
+        assert!(!info.is_relative_original());
+    }
+
+    #[test]
+    fn with_encode_policy() {
+        let date = parse_trash_datetime("2023-06-01T12:30:45").unwrap();
+        let info = TrashInfo::new(Path::new("/a b/c"), date);
+        assert_eq!(info.path(), "/a%20b/c");
+        let minimal = info.clone().with_encode_policy(EncodePolicy::Minimal);
+        assert_eq!(minimal.path(), "/a b/c");
+        assert_eq!(minimal.path_decoded(), info.path_decoded());
+        let legacy = info.with_encode_policy(EncodePolicy::Legacy);
+        assert_eq!(parse(&legacy.to_string()).unwrap().path(), "%2Fa%20b%2Fc");
+    }
+
+    #[test]
+    fn new_truncates_to_seconds() {
+        let date = parse_trash_datetime("2023-06-01T12:30:45").unwrap();
+        let precise = date + chrono::Duration::nanoseconds(999_999_999);
+        let info = TrashInfo::new(Path::new("/a"), precise);
+        assert_eq!(info.deletion_date(), date);
+        assert_eq!(parse(&info.to_string()), Ok(info.clone()));
+        assert_eq!(info.with_deletion_date(precise).deletion_date(), date);
+    }
+
+    #[test]
+    fn display_round_trip() {
+        let s = "[Trash Info]\nPath=/home/user/a%0Ab\nDeletionDate=2023-06-01T12:30:45\n";
+        let info = parse(s).unwrap();
+        assert_eq!(info.to_string(), s);
+        assert_eq!(parse(&info.to_string()), Ok(info));
+    }
+
+    #[test]
+    fn display_escapes_control_characters() {
+        let info =
+            parse("[Trash Info]\nPath=/a\rb\tc\nDeletionDate=2023-06-01T12:30:45\n").unwrap();
+        assert_eq!(
+            info.to_string(),
+            "[Trash Info]\nPath=/a%0Db%09c\nDeletionDate=2023-06-01T12:30:45\n"
+        );
+        assert_eq!(parse(&info.to_string()).unwrap().path_decoded(), Path::new("/a\rb\tc"));
+
+        let name = Path::new("new\nline");
+        let s = format!(
+            "[Trash Info]\nPath=/{}\nDeletionDate=2023-06-01T12:30:45\n",
+            encoding::encode_path(name)
+        );
+        let info = parse(&s).unwrap();
+        assert_eq!(info.to_string(), s);
+        assert_eq!(info.path_decoded(), Path::new("/").join(name));
+    }
+
+    #[test]
+    fn parse_leading_bom_and_blank_lines() {
+        for fixture in [
+            include_str!("../tests/fixtures/bom.trashinfo"),
+            include_str!("../tests/fixtures/leading_blank_lines.trashinfo"),
+        ] {
+            let info = parse(fixture).unwrap();
+            assert_eq!(info.path_decoded(), Path::new("/home/user/some file.txt"));
+            assert_eq!(info.deletion_date().to_string(), "2023-06-01 12:30:45");
+        }
+        assert_eq!(
+            parse("\u{feff}\n\nPath=/a\nDeletionDate=2023-06-01T12:30:45"),
+            Err(ParseError::MissingHeader)
+        );
+        assert_eq!(parse("\u{feff}"), Err(ParseError::MissingHeader));
+    }
+
+    #[test]
+    fn parse_errors() {
+        assert_eq!(parse(""), Err(ParseError::MissingHeader));
+        assert_eq!(parse_trash_info(""), Err(ParseError::MissingHeader));
+        assert_eq!(
+            parse("Path=/a\nDeletionDate=2023-06-01T12:30:45"),
+            Err(ParseError::MissingHeader)
+        );
+        assert_eq!(
+            parse("[Trash Info]\nDeletionDate=2023-06-01T12:30:45"),
+            Err(ParseError::MissingPath)
+        );
+        assert_eq!(parse("[Trash Info]\nPath=/a"), Err(ParseError::MissingDeletionDate));
+        assert_eq!(
+            parse("[Trash Info]\nPath=/a\nDeletionDate=yesterday"),
+            Err(ParseError::InvalidDeletionDate("yesterday".into()))
+        );
+        assert_eq!(
+            parse("[Trash Info]\nPath\nDeletionDate=2023-06-01T12:30:45"),
+            Err(ParseError::InvalidLine("Path".into()))
+        );
+    }
+}
@@ -1,7 +1,18 @@
+use std::{
+    ffi::OsStr,
+    fs, io,
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+use chrono::{Duration, TimeZone, Utc};
 use either::Either;
 use trash::TrashItem;
 
-use crate::utils::swap;
+use crate::{
+    clock::Clock,
+    utils::{self, swap},
+};
 
 pub struct MaybeIndexedTrashItems(pub Either<Vec<TrashItem>, Vec<(u32, TrashItem)>>);
 
@@ -24,3 +35,68 @@ impl MaybeIndexedTrashItems {
         self.0.as_ref().either(|v| v.len(), |v| v.len())
     }
 }
+
+/// Returns how long ago `item` was trashed, according to `clock`.
+pub fn age(item: &TrashItem, clock: &impl Clock) -> Duration {
+    clock.now_local().signed_duration_since(Utc.timestamp_opt(item.time_deleted, 0).unwrap())
+}
+
+/// Returns when the info file of `item` was last written, which can differ from its deletion date.
+///
+/// This is what the `directorysizes` cache is checked against.
+pub fn info_mtime(item: &TrashItem) -> io::Result<SystemTime> {
+    fs::metadata(&item.id)?.modified()
+}
+
+/// Yields `items` sorted by deletion time, newest first.
+///
+/// Deletion times only have a precision of seconds, so items trashed in the same second are
+/// ordered by their trashed name and then by their trash directory. This keeps the order the
+/// same between runs, no matter in which order the trash directories list their files.
+///
+/// Unlike iterating over the trash directly, this has to buffer every item, because the order is
+/// only known once all deletion times have been read.
+pub fn newest_first(
+    mut items: Vec<TrashItem>,
+) -> impl DoubleEndedIterator<Item = TrashItem> + ExactSizeIterator {
+    items.sort_by(|a, b| {
+        b.time_deleted
+            .cmp(&a.time_deleted)
+            .then_with(|| trashed_name(a).cmp(trashed_name(b)))
+            .then_with(|| a.id.cmp(&b.id))
+    });
+    items.into_iter()
+}
+
+/// The name of the file of `item` inside its trash directory.
+///
+/// On linux the id of an item is the path of its info file, which is named after the file.
+fn trashed_name(item: &TrashItem) -> &OsStr {
+    Path::new(&item.id).file_stem().unwrap_or(&item.id)
+}
+
+/// Whether two items were trashed from the same original path.
+pub fn same_origin(item: &TrashItem, other: &TrashItem) -> bool {
+    origin(item) == origin(other)
+}
+
+/// Groups items that were trashed from the same original path.
+///
+/// Only groups with more than one item are returned, sorted by original path.
+pub fn find_duplicates(items: &[TrashItem]) -> Vec<Vec<&TrashItem>> {
+    let mut items: Vec<_> = items.iter().collect();
+    items.sort_by_cached_key(|item| origin(item));
+    let mut groups: Vec<Vec<&TrashItem>> = Vec::new();
+    for item in items {
+        match groups.last_mut() {
+            Some(group) if same_origin(group[0], item) => group.push(item),
+            _ => groups.push(vec![item]),
+        }
+    }
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+fn origin(item: &TrashItem) -> PathBuf {
+    utils::path::normalize(&item.original_path())
+}
@@ -0,0 +1,56 @@
+use std::{
+    fmt,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use anyhow::{bail, Result};
+
+use crate::{trash_dir::TrashDir, utils};
+
+/// Identifies a single entry that was just put into the trash, so that exactly that entry can be
+/// restored again.
+///
+/// Other entries with the same original path, even ones trashed later, are never touched.
+/// A token is used up by [`UndoToken::undo`], and it becomes invalid as soon as its entry is
+/// restored or removed from the trash in some other way.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UndoToken {
+    trash_dir: TrashDir,
+    info_path: PathBuf,
+}
+
+impl UndoToken {
+    pub fn new(trash_dir: TrashDir, info_path: PathBuf) -> UndoToken {
+        UndoToken { trash_dir, info_path }
+    }
+
+    /// Restores the entry of this token, returning the path it was restored to.
+    pub fn undo(self) -> Result<PathBuf> {
+        if !self.info_path.is_file() {
+            bail!("The entry {self} is no longer in the trash");
+        }
+        let item = self.trash_dir.read_item(&self.info_path)?;
+        let original_path = self.trash_dir.original_location(&self.info_path)?;
+        trash::os_limited::restore_all([item])?;
+        Ok(original_path)
+    }
+}
+
+impl fmt::Display for UndoToken {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", utils::path::display(&self.info_path))
+    }
+}
+
+impl FromStr for UndoToken {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let info_path = Path::new(s);
+        match TrashDir::containing(info_path) {
+            Some(trash_dir) => Ok(UndoToken::new(trash_dir, info_path.to_path_buf())),
+            None => Err(format!("{s} is not an info file of a known trash directory")),
+        }
+    }
+}
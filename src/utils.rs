@@ -1,5 +1,5 @@
-use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use std::{env, ffi::OsString, fs, io};
 
 use lscolors::{LsColors, Style};
 use once_cell::sync::Lazy;
@@ -14,8 +14,213 @@ pub mod path {
         path.as_os_str().to_string_lossy().to_string()
     }
 
+    /// Shortens `path` to at most `max` characters by replacing its middle with `…`.
+    ///
+    /// The leading components and the file name stay visible for as long as they fit, and when
+    /// not even the file name fits, only its end is kept. Characters are never split.
+    pub fn shorten_path(path: &str, max: usize) -> String {
+        let len = path.chars().count();
+        if len <= max {
+            return path.to_string();
+        }
+        if max == 0 {
+            return String::new();
+        }
+        let tail = match path.rfind('/') {
+            Some(i) if i > 0 => &path[i..],
+            _ => path,
+        };
+        let tail_len = tail.chars().count();
+        if tail_len + 1 >= max {
+            let kept: String = tail.chars().skip(tail_len + 1 - max).collect();
+            return format!("…{kept}");
+        }
+        let head: String = path.chars().take(max - 1 - tail_len).collect();
+        format!("{head}…{tail}")
+    }
+
+    pub use trashy::path::normalize;
+
+    /// Makes `path` absolute the same way the trash crate does, resolving only its parent.
+    ///
+    /// This is the original path that is recorded when `path` is trashed. Its parent has to exist.
+    pub fn canonicalize_parent(path: &Path) -> Option<PathBuf> {
+        let path = env::current_dir().ok()?.join(path);
+        let parent = fs::canonicalize(path.parent()?).ok()?;
+        Some(match path.file_name() {
+            Some(file_name) => parent.join(file_name),
+            None => parent,
+        })
+    }
+
+    /// Returns the size of `path`, including everything inside it if it is a directory.
+    ///
+    /// Symlinks are counted as themselves and never followed, so a symlink pointing back up the
+    /// tree can't make this go around in circles.
+    pub fn size(path: &Path) -> io::Result<u64> {
+        let meta = fs::symlink_metadata(path)?;
+        if !meta.is_dir() {
+            return Ok(meta.len());
+        }
+        let mut size = meta.len();
+        for entry in fs::read_dir(path)? {
+            size += self::size(&entry?.path())?;
+        }
+        Ok(size)
+    }
+
+    /// Returns the SHA-256 of the contents of `path` as lowercase hex digits.
+    ///
+    /// The file is read in chunks, so it does not have to fit into memory.
+    pub fn sha256(path: &Path) -> io::Result<String> {
+        use sha2::{Digest, Sha256};
+        use std::io::Read;
+
+        let mut file = fs::File::open(path)?;
+        let mut hasher = Sha256::new();
+        let mut buf = vec![0; 64 * 1024];
+        loop {
+            match file.read(&mut buf)? {
+                0 => break,
+                n => hasher.update(&buf[..n]),
+            }
+        }
+        Ok(format!("{:x}", hasher.finalize()))
+    }
+
+    /// Copies `from` to `to`, including everything inside it if it is a directory.
+    ///
+    /// Symlinks are copied as links and never followed. With the `xattr` feature on Linux, the
+    /// extended attributes are copied too, like SELinux labels.
+    pub fn copy_all(from: &Path, to: &Path) -> io::Result<()> {
+        let meta = fs::symlink_metadata(from)?;
+        if meta.is_dir() {
+            fs::create_dir(to)?;
+            for entry in fs::read_dir(from)? {
+                let entry = entry?;
+                copy_all(&entry.path(), &to.join(entry.file_name()))?;
+            }
+            fs::set_permissions(to, meta.permissions())?;
+        } else if meta.file_type().is_symlink() {
+            copy_symlink(from, to)?;
+        } else {
+            fs::copy(from, to)?;
+        }
+        copy_xattrs(from, to)
+    }
+
+    /// Copies the extended attributes of `from` to `to`, without following symlinks.
+    ///
+    /// Attributes that `to` can't have, because its filesystem does not support them or only
+    /// root may set them, are left out.
+    #[cfg(all(target_os = "linux", feature = "xattr"))]
+    fn copy_xattrs(from: &Path, to: &Path) -> io::Result<()> {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+        let c_path = |path: &Path| {
+            CString::new(path.as_os_str().as_bytes())
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))
+        };
+        let (from, to) = (c_path(from)?, c_path(to)?);
+        // every call is made twice, first to get the size of the buffer
+        let read = |f: &dyn Fn(*mut libc::c_void, usize) -> isize| -> io::Result<Vec<u8>> {
+            let len = f(std::ptr::null_mut(), 0);
+            if len < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let mut buf = vec![0u8; len as usize];
+            let len = f(buf.as_mut_ptr().cast(), buf.len());
+            if len < 0 {
+                return Err(io::Error::last_os_error());
+            }
+            buf.truncate(len as usize);
+            Ok(buf)
+        };
+        let names =
+            match read(&|buf, len| unsafe { libc::llistxattr(from.as_ptr(), buf.cast(), len) }) {
+                Ok(names) => names,
+                Err(e) if e.raw_os_error() == Some(libc::ENOTSUP) => return Ok(()),
+                Err(e) => return Err(e),
+            };
+        for name in names.split(|b| *b == 0).filter(|name| !name.is_empty()) {
+            let name = CString::new(name).expect("BUG: xattr names are separated by nul bytes");
+            let value = read(&|buf, len| unsafe {
+                libc::lgetxattr(from.as_ptr(), name.as_ptr(), buf, len)
+            })?;
+            let set = unsafe {
+                libc::lsetxattr(to.as_ptr(), name.as_ptr(), value.as_ptr().cast(), value.len(), 0)
+            };
+            if set != 0 {
+                let e = io::Error::last_os_error();
+                match e.raw_os_error() {
+                    Some(libc::ENOTSUP | libc::EPERM) => continue,
+                    _ => return Err(e),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(not(all(target_os = "linux", feature = "xattr")))]
+    fn copy_xattrs(_: &Path, _: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+        std::os::unix::fs::symlink(fs::read_link(from)?, to)
+    }
+
+    #[cfg(not(unix))]
+    fn copy_symlink(from: &Path, to: &Path) -> io::Result<()> {
+        fs::copy(from, to).map(|_| ())
+    }
+
+    /// Gives `path` to the user `uid` and the group `gid`, keeping the ids that are `None`.
+    ///
+    /// A symlink itself is changed, not the file it points to.
+    #[cfg(unix)]
+    pub fn set_owner(path: &Path, uid: Option<u32>, gid: Option<u32>) -> io::Result<()> {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+        let path = CString::new(path.as_os_str().as_bytes())
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+        // -1 leaves an id unchanged
+        let uid = uid.unwrap_or(u32::MAX);
+        let gid = gid.unwrap_or(u32::MAX);
+        match unsafe { libc::lchown(path.as_ptr(), uid, gid) } {
+            0 => Ok(()),
+            _ => Err(io::Error::last_os_error()),
+        }
+    }
+
+    #[cfg(not(unix))]
+    pub fn set_owner(_: &Path, _: Option<u32>, _: Option<u32>) -> io::Result<()> {
+        Err(io::Error::new(io::ErrorKind::Unsupported, "Files have no owner ids on this platform"))
+    }
+
+    /// Removes `path`, including everything inside it if it is a directory.
+    pub fn remove_all(path: &Path) -> io::Result<()> {
+        if fs::symlink_metadata(path)?.is_dir() {
+            fs::remove_dir_all(path)
+        } else {
+            fs::remove_file(path)
+        }
+    }
+
+    /// Returns the file name of `path` with ` (n)` inserted before its extension.
+    pub fn numbered_name(path: &Path, n: u32) -> OsString {
+        let mut name = path.file_stem().unwrap_or_default().to_os_string();
+        name.push(format!(" ({n})"));
+        if let Some(extension) = path.extension() {
+            name.push(".");
+            name.push(extension);
+        }
+        name
+    }
+
     pub fn style_for<'a>(path: &Path, metadata: &'a fs::Metadata) -> Option<&'a Style> {
-        LS_COLORS.style_for_path_with_metadata(&path, Some(metadata))
+        LS_COLORS.style_for_path_with_metadata(path, Some(metadata))
     }
 }
 
@@ -28,6 +233,179 @@ pub fn clone_trash_item(item: &TrashItem) -> TrashItem {
     }
 }
 
+/// Formats a number of bytes using binary units, like `4.0 KiB`.
+pub fn human_size(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["B", "KiB", "MiB", "GiB", "TiB", "PiB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{bytes} B")
+    } else {
+        format!("{size:.1} {}", UNITS[unit])
+    }
+}
+
+/// Parses a size like `500`, `10K`, or `1GiB`, where every unit is a power of 1024.
+pub fn parse_size(s: &str) -> Result<u64, String> {
+    let s = s.trim();
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (number, unit) = s.split_at(split);
+    let number =
+        number.parse::<u64>().map_err(|_| format!("`{s}` does not start with a number"))?;
+    let exponent = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 0,
+        "k" | "kb" | "kib" => 1,
+        "m" | "mb" | "mib" => 2,
+        "g" | "gb" | "gib" => 3,
+        "t" | "tb" | "tib" => 4,
+        unit => return Err(format!("`{unit}` is not a unit of size")),
+    };
+    number.checked_mul(1024u64.pow(exponent)).ok_or_else(|| format!("`{s}` is too large"))
+}
+
 pub fn swap<T, U>((t, u): (T, U)) -> (U, T) {
     (u, t)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered_name() {
+        assert_eq!(path::numbered_name(Path::new("/a/b.txt"), 1), "b (1).txt");
+        assert_eq!(path::numbered_name(Path::new("/a/b.tar.gz"), 2), "b.tar (2).gz");
+        assert_eq!(path::numbered_name(Path::new("/a/b"), 3), "b (3)");
+        assert_eq!(path::numbered_name(Path::new("/a/.b"), 1), ".b (1)");
+    }
+
+    #[test]
+    fn shorten_path() {
+        let path = "/home/user/projects/trashy/src/main.rs";
+        assert_eq!(path::shorten_path(path, 100), path);
+        assert_eq!(path::shorten_path(path, path.len()), path);
+        assert_eq!(path::shorten_path(path, 30), "/home/user/projects/t…/main.rs");
+        assert_eq!(path::shorten_path(path, 12), "/ho…/main.rs");
+        assert_eq!(path::shorten_path(path, 9), "…/main.rs");
+        assert_eq!(path::shorten_path(path, 5), "…n.rs");
+        assert_eq!(path::shorten_path(path, 1), "…");
+        assert_eq!(path::shorten_path(path, 0), "");
+        assert_eq!(path::shorten_path("no_separator_at_all", 8), "…_at_all");
+
+        // multibyte characters are counted and kept whole
+        let path = "/höme/ünïcödé/dörectory/fïlé.txt";
+        assert_eq!(path::shorten_path(path, 20), "/höme/ünïc…/fïlé.txt");
+        assert_eq!(path::shorten_path(path, 6), "…é.txt");
+        for max in 0..40 {
+            assert!(path::shorten_path(path, max).chars().count() <= max);
+        }
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn size_with_symlink_loops() {
+        use std::os::unix::fs::symlink;
+
+        let dir = tempfile::tempdir().unwrap();
+        let tree = dir.path().join("tree");
+        fs::create_dir_all(tree.join("sub")).unwrap();
+        fs::write(tree.join("sub/file"), "abc").unwrap();
+        symlink(".", tree.join("self")).unwrap();
+        symlink("..", tree.join("sub/parent")).unwrap();
+        symlink("loop", tree.join("loop")).unwrap();
+
+        let dir_sizes = [&tree, &tree.join("sub")]
+            .iter()
+            .map(|dir| fs::symlink_metadata(dir).unwrap().len())
+            .sum::<u64>();
+        let link_sizes = ["self", "sub/parent", "loop"]
+            .iter()
+            .map(|link| fs::symlink_metadata(tree.join(link)).unwrap().len())
+            .sum::<u64>();
+        assert_eq!(path::size(&tree).unwrap(), dir_sizes + 3 + link_sizes);
+        assert_eq!(path::size(&tree.join("self")).unwrap(), 1);
+    }
+
+    #[cfg(all(target_os = "linux", feature = "xattr"))]
+    #[test]
+    fn copy_all_keeps_xattrs() {
+        use std::{ffi::CString, os::unix::ffi::OsStrExt};
+
+        let xattr = |path: &Path| {
+            let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+            let mut buf = [0u8; 16];
+            let len = unsafe {
+                libc::lgetxattr(
+                    path.as_ptr(),
+                    b"user.origin\0".as_ptr().cast(),
+                    buf.as_mut_ptr().cast(),
+                    buf.len(),
+                )
+            };
+            (len >= 0).then(|| buf[..len as usize].to_vec())
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let from = dir.path().join("from");
+        fs::create_dir(&from).unwrap();
+        fs::write(from.join("file"), "abc").unwrap();
+        for path in [&from, &from.join("file")] {
+            let path = CString::new(path.as_os_str().as_bytes()).unwrap();
+            let set = unsafe {
+                libc::lsetxattr(
+                    path.as_ptr(),
+                    b"user.origin\0".as_ptr().cast(),
+                    b"x".as_ptr().cast(),
+                    1,
+                    0,
+                )
+            };
+            assert_eq!(set, 0, "{}", io::Error::last_os_error());
+        }
+
+        let to = dir.path().join("to");
+        path::copy_all(&from, &to).unwrap();
+        assert_eq!(xattr(&to), Some(b"x".to_vec()));
+        assert_eq!(xattr(&to.join("file")), Some(b"x".to_vec()));
+    }
+
+    #[test]
+    fn sha256() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("file");
+        fs::write(&path, "abc").unwrap();
+        assert_eq!(
+            path::sha256(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+        // larger than one chunk
+        fs::write(&path, vec![b'a'; 1_000_000]).unwrap();
+        assert_eq!(
+            path::sha256(&path).unwrap(),
+            "cdc76e5c9914fb9281a1c7e284d73e67f1809a48a497200e046d39ccc7112cd0"
+        );
+    }
+
+    #[test]
+    fn parse_size() {
+        assert_eq!(super::parse_size("500"), Ok(500));
+        assert_eq!(super::parse_size("10K"), Ok(10 * 1024));
+        assert_eq!(super::parse_size("1GiB"), Ok(1024 * 1024 * 1024));
+        assert_eq!(super::parse_size("2 mb"), Ok(2 * 1024 * 1024));
+        assert!(super::parse_size("GiB").is_err());
+        assert!(super::parse_size("1x").is_err());
+        assert!(super::parse_size("99999999999T").is_err());
+    }
+
+    #[test]
+    fn human_size() {
+        assert_eq!(super::human_size(0), "0 B");
+        assert_eq!(super::human_size(1023), "1023 B");
+        assert_eq!(super::human_size(1024), "1.0 KiB");
+        assert_eq!(super::human_size(1536), "1.5 KiB");
+        assert_eq!(super::human_size(5 * 1024 * 1024 * 1024), "5.0 GiB");
+    }
+}